@@ -0,0 +1,149 @@
+/*
+ * C API of siarne, fast simulation of artificial neurons.
+ * Build the crate with the `capi` feature and link against libsiarne.a or libsiarne.so.
+ */
+
+#ifndef SIARNE_H
+#define SIARNE_H
+
+/* Generated from src/capi.rs by cbindgen, see tests/header.rs. Do not edit. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * Opaque handle, must be freed with siarne_network_free.
+ */
+typedef struct SiarneNetwork SiarneNetwork;
+
+/**
+ * Error codes of the C API, the first variants correspond to [network::Error].
+ */
+typedef enum {
+  SIARNE_ERROR_OK = 0,
+  SIARNE_ERROR_ZERO_NEURONS = 1,
+  SIARNE_ERROR_ZERO_CONNECTIONS = 2,
+  SIARNE_ERROR_TOO_MANY_CONNECTIONS = 3,
+  SIARNE_ERROR_EFFECT_COUNT_OVERFLOW = 4,
+  SIARNE_ERROR_INVALID_NEURON_INDEX = 5,
+  SIARNE_ERROR_INVALID_NEURON_TYPES = 6,
+  SIARNE_ERROR_EFFECT_SIGN = 7,
+  SIARNE_ERROR_INVALID_CONNECTION_MASK = 8,
+  SIARNE_ERROR_DISABLED_EFFECT = 9,
+  /**
+   * A null pointer was passed where it is not allowed.
+   */
+  SIARNE_ERROR_NULL = 100,
+  /**
+   * A buffer length does not match the network.
+   */
+  SIARNE_ERROR_INVALID_LENGTH = 101,
+  /**
+   * A path is not valid UTF-8.
+   */
+  SIARNE_ERROR_INVALID_PATH = 102,
+  /**
+   * Reading or writing a file failed.
+   */
+  SIARNE_ERROR_IO = 103,
+} SiarneError;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Create a network with parameters generated from `seed`, see [build_network_from_noise].
+ * # Safety
+ * `out` must be valid for writes.
+ */
+SiarneError siarne_network_new(size_t neuron_count,
+                               size_t connection_count,
+                               size_t input_count,
+                               size_t output_count,
+                               uint64_t seed,
+                               SiarneNetwork **out);
+
+/**
+ * Create a network from the specified parameters, see [Network::with_params].
+ * # Safety
+ * Every pointer must be valid for reads of its length, `out` must be valid for writes.
+ */
+SiarneError siarne_network_from_params(const int32_t *tresholds,
+                                       size_t neuron_count,
+                                       const int8_t *effects,
+                                       size_t effect_count,
+                                       const size_t *input_neurons,
+                                       size_t input_count,
+                                       const size_t *output_neurons,
+                                       size_t output_count,
+                                       SiarneNetwork **out);
+
+/**
+ * Load a network from a params file, see [NetworkParams::load].
+ * # Safety
+ * `path` must be a null terminated string, `out` must be valid for writes.
+ */
+SiarneError siarne_network_load(const char *path_str, SiarneNetwork **out);
+
+/**
+ * Save the parameters of a network to a file, see [NetworkParams::save].
+ * # Safety
+ * `net` must be a network created by this API, `path` must be a null terminated string.
+ */
+SiarneError siarne_network_save(const SiarneNetwork *net, const char *path_str);
+
+/**
+ * Free a network, does nothing when `net` is null.
+ * # Safety
+ * `net` must be null or a network created by this API that is not freed yet.
+ */
+void siarne_network_free(SiarneNetwork *net);
+
+/**
+ * The amount of neurons of a network.
+ * # Safety
+ * `net` must be a network created by this API.
+ */
+size_t siarne_network_neuron_count(const SiarneNetwork *net);
+
+/**
+ * The amount of input neurons of a network.
+ * # Safety
+ * `net` must be a network created by this API.
+ */
+size_t siarne_network_input_count(const SiarneNetwork *net);
+
+/**
+ * The amount of output neurons of a network.
+ * # Safety
+ * `net` must be a network created by this API.
+ */
+size_t siarne_network_output_count(const SiarneNetwork *net);
+
+/**
+ * Apply inputs to the input neurons, see [Network::apply_inputs].
+ * # Safety
+ * `net` must be a network created by this API, `inputs` must be valid for reads of `len` values.
+ */
+SiarneError siarne_network_apply_inputs(SiarneNetwork *net, const int32_t *inputs, size_t len);
+
+/**
+ * Execute a tick, see [Network::tick].
+ * # Safety
+ * `net` must be a network created by this API.
+ */
+void siarne_network_tick(SiarneNetwork *net);
+
+/**
+ * Read the values of the output neurons, see [Network::read_outputs].
+ * # Safety
+ * `net` must be a network created by this API, `outputs` must be valid for writes of `len` values.
+ */
+SiarneError siarne_network_read_outputs(const SiarneNetwork *net, int32_t *outputs, size_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SIARNE_H */
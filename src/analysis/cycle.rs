@@ -0,0 +1,154 @@
+//! Detection of fixed points and limit cycles.
+
+use super::{hash_state, Cycle};
+use crate::network::{Network, NeuronValue};
+
+/// A copy of the accumulator state of a network with its hash, for fast comparison.
+struct State {
+    hash: u64,
+    accumulators: Box<[NeuronValue]>,
+}
+
+impl State {
+    fn of(net: &Network) -> Self {
+        Self {
+            hash: hash_state(net.accumulators()),
+            accumulators: net.accumulators().into(),
+        }
+    }
+
+    fn matches(&self, net: &Network) -> bool {
+        hash_state(net.accumulators()) == self.hash && *self.accumulators == *net.accumulators()
+    }
+
+    /// The state after a tick from this state.
+    fn next(&self, net: &mut Network) -> Self {
+        net.set_accumulators(&self.accumulators);
+        net.tick();
+        Self::of(net)
+    }
+}
+
+/// Tick `net` until its accumulator state repeats, using Brent's cycle detection.  
+/// Without inputs and plasticity a tick only depends on the accumulator state, so once a state
+/// repeats the network keeps cycling. Memory use does not depend on the length of the cycle,
+/// and states are compared exactly so hash collisions do not cause false results.  
+/// Returns `None` when no repetition is found within `max_ticks` ticks, otherwise `net` is left
+/// in the first state of the cycle.
+/// # Panics
+/// When [Plasticity](crate::network::Plasticity) is enabled, since the effects change during ticks.
+pub fn find_cycle(net: &mut Network, max_ticks: u64) -> Option<Cycle> {
+    assert!(!net.params().plasticity.enabled);
+
+    let start = State::of(net);
+
+    // find the period by moving the tortoise to the hare at every power of two
+    let mut tortoise = State::of(net);
+    let mut power = 1;
+    let mut period = 1;
+    let mut ticks = 1;
+    net.tick();
+    while !tortoise.matches(net) {
+        if ticks >= max_ticks {
+            return None;
+        }
+        if power == period {
+            tortoise = State::of(net);
+            power *= 2;
+            period = 0;
+        }
+        net.tick();
+        period += 1;
+        ticks += 1;
+    }
+
+    // find the transient with two runners a period apart
+    let mut tortoise = start;
+    net.set_accumulators(&tortoise.accumulators);
+    for _ in 0..period {
+        net.tick();
+    }
+    let mut hare = State::of(net);
+
+    let mut transient = 0;
+    while tortoise.hash != hare.hash || tortoise.accumulators != hare.accumulators {
+        tortoise = tortoise.next(net);
+        hare = hare.next(net);
+        transient += 1;
+    }
+    net.set_accumulators(&tortoise.accumulators);
+
+    Some(Cycle { transient, period })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{Effect, NetworkParams, Plasticity};
+    use crate::train::evolve::build_network_from_noise;
+
+    use std::iter;
+
+    /// Find the cycle by remembering every state.
+    fn naive_cycle(net: &mut Network) -> Cycle {
+        let mut states: Vec<Box<[NeuronValue]>> = vec![];
+        loop {
+            if let Some(seen) = states.iter().position(|s| **s == *net.accumulators()) {
+                return Cycle {
+                    transient: seen as u64,
+                    period: (states.len() - seen) as u64,
+                };
+            }
+            states.push(net.accumulators().into());
+            net.tick();
+        }
+    }
+
+    #[test]
+    fn brent() {
+        let params = NetworkParams {
+            tresholds: vec![NeuronValue(0), NeuronValue(16), NeuronValue(-16)].into(),
+            effects: vec![
+                Effect(-8), Effect(1), Effect(2),
+                Effect(-17), Effect(127), Effect(0),
+                Effect(127), Effect(0), Effect(0),
+            ].into(),
+            input_neurons: vec![].into(),
+            output_neurons: vec![].into(),
+            plasticity: Plasticity::default(),
+            neuron_types: None,
+            connection_mask: None,
+        };
+        let mut nets = vec![Network::with_params(params).unwrap()];
+        for seed in 0..20 {
+            let mut net = build_network_from_noise(12, 5, 0, 0, seed, iter::empty()).unwrap();
+            let mut params = net.extract_params();
+            // small tresholds so the networks do something interesting
+            for treshold in params.tresholds.iter_mut() {
+                treshold.0 >>= 24;
+            }
+            net = Network::with_params(params).unwrap();
+            nets.push(net);
+        }
+
+        for mut net in nets {
+            let initial = State::of(&net);
+            let expected = naive_cycle(&mut net);
+            net.set_accumulators(&initial.accumulators);
+
+            let cycle = find_cycle(&mut net, 10_000).unwrap();
+            assert_eq!(cycle, expected);
+
+            let first = State::of(&net);
+            for _ in 0..cycle.period {
+                net.tick();
+            }
+            assert!(first.matches(&net));
+
+            net.set_accumulators(&initial.accumulators);
+            if cycle.transient + cycle.period > 1 {
+                assert_eq!(find_cycle(&mut net, 1), None);
+            }
+        }
+    }
+}
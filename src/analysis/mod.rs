@@ -0,0 +1,26 @@
+//! Code related to measuring the behaviour of running [Network](crate::Network)s.
+
+pub mod cycle;
+pub mod stats;
+
+use crate::network::NeuronValue;
+
+/// A repetition of the accumulator state of a network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    /// The amount of ticks before the first state that repeats.
+    pub transient: u64,
+    /// The amount of ticks after which the state repeats, 1 for a fixed point.
+    pub period: u64,
+}
+
+/// A FNV-1a hash of the accumulator state of a network, which is stable across platforms and runs.
+pub(crate) fn hash_state(accumulators: &[NeuronValue]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    accumulators
+        .iter()
+        .flat_map(|value| value.0.to_le_bytes())
+        .fold(OFFSET_BASIS, |hash, byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+}
@@ -0,0 +1,208 @@
+//! Activity statistics and health metrics of a running network.
+
+use super::{hash_state, Cycle};
+use crate::network::Network;
+
+use std::collections::{HashMap, VecDeque};
+
+/// Collects statistics about the activity of a [Network] over ticks.  
+/// Besides firing rates and accumulator ranges it detects when the network
+/// enters a fixed point or a limit cycle with a period of at most `max_period` ticks,
+/// by comparing hashes of the accumulator state. In rare cases a hash collision can cause
+/// a cycle to be reported that does not exist.
+/// # Examples
+/// ```
+/// # use siarne::{Network, analysis::stats::NetworkStats};
+/// // reject networks that do nothing interesting before evaluating them properly
+/// let mut net = Network::new(64, 8, 0, 0).unwrap();
+/// let mut stats = NetworkStats::new(64, 16);
+/// for _ in 0..100 {
+///     stats.tick(&mut net);
+/// }
+/// let fitness = if stats.is_degenerate() {
+///     f64::NEG_INFINITY
+/// } else {
+///     // the actual fitness function, possibly using the stats as auxiliary terms
+///     stats.firing_rates().sum::<f64>()
+/// };
+/// ```
+pub struct NetworkStats {
+    ticks: u64,
+    fire_counts: Box<[u64]>,
+    accumulator_min: i32,
+    accumulator_max: i32,
+    accumulator_sum: i128,
+    max_period: u64,
+    /// Hashes of recent states with the last tick they were seen at.
+    recent_states: HashMap<u64, u64>,
+    recent_order: VecDeque<(u64, u64)>,
+    cycle: Option<Cycle>,
+}
+
+impl NetworkStats {
+    /// Create an empty collector for networks with `neuron_count` neurons.
+    pub fn new(neuron_count: usize, max_period: u64) -> Self {
+        Self {
+            ticks: 0,
+            fire_counts: vec![0; neuron_count].into(),
+            accumulator_min: i32::MAX,
+            accumulator_max: i32::MIN,
+            accumulator_sum: 0,
+            max_period,
+            recent_states: HashMap::new(),
+            recent_order: VecDeque::new(),
+            cycle: None,
+        }
+    }
+
+    /// Record the current state of `net`.
+    /// # Panics
+    /// When `net` has a different amount of neurons than this collector.
+    pub fn record(&mut self, net: &Network) {
+        assert_eq!(net.params().tresholds.len(), self.fire_counts.len());
+
+        for (count, firing) in self.fire_counts.iter_mut().zip(net.firing()) {
+            *count += firing as u64;
+        }
+        for value in net.accumulators() {
+            self.accumulator_min = self.accumulator_min.min(value.0);
+            self.accumulator_max = self.accumulator_max.max(value.0);
+            self.accumulator_sum += value.0 as i128;
+        }
+
+        if self.cycle.is_none() && self.max_period > 0 {
+            let hash = hash_state(net.accumulators());
+            if let Some(&seen) = self.recent_states.get(&hash) {
+                self.cycle = Some(Cycle {
+                    transient: seen,
+                    period: self.ticks - seen,
+                });
+            }
+
+            self.recent_states.insert(hash, self.ticks);
+            self.recent_order.push_back((self.ticks, hash));
+            while let Some(&(tick, hash)) = self.recent_order.front() {
+                if self.ticks - tick < self.max_period {
+                    break;
+                }
+                self.recent_order.pop_front();
+                if self.recent_states.get(&hash) == Some(&tick) {
+                    self.recent_states.remove(&hash);
+                }
+            }
+        }
+
+        self.ticks += 1;
+    }
+
+    /// Execute a tick on the network and record the result.
+    pub fn tick(&mut self, net: &mut Network) {
+        net.tick();
+        self.record(net);
+    }
+
+    /// The amount of recorded states.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// The amount of recorded states in which each neuron was firing, see [Network::firing].
+    pub fn fire_counts(&self) -> &[u64] {
+        &self.fire_counts
+    }
+
+    /// The fraction of recorded states in which each neuron was firing.
+    pub fn firing_rates(&self) -> impl Iterator<Item = f64> + '_ {
+        let ticks = self.ticks.max(1) as f64;
+        self.fire_counts.iter().map(move |&count| count as f64 / ticks)
+    }
+
+    /// The fraction of neurons that never fired.
+    pub fn silent_fraction(&self) -> f64 {
+        self.fraction(|count| count == 0)
+    }
+
+    /// The fraction of neurons that fired in every recorded state.
+    pub fn saturated_fraction(&self) -> f64 {
+        self.fraction(|count| count == self.ticks)
+    }
+
+    /// The lowest accumulator value of any neuron, `None` when nothing is recorded.
+    pub fn accumulator_min(&self) -> Option<i32> {
+        Some(self.accumulator_min).filter(|_| self.ticks > 0)
+    }
+
+    /// The highest accumulator value of any neuron, `None` when nothing is recorded.
+    pub fn accumulator_max(&self) -> Option<i32> {
+        Some(self.accumulator_max).filter(|_| self.ticks > 0)
+    }
+
+    /// The mean accumulator value over all neurons and recorded states.
+    pub fn accumulator_mean(&self) -> f64 {
+        let count = self.ticks as f64 * self.fire_counts.len() as f64;
+        if count == 0.0 {
+            0.0
+        } else {
+            self.accumulator_sum as f64 / count
+        }
+    }
+
+    /// The first repetition of the accumulator state, if any was detected.
+    pub fn cycle(&self) -> Option<Cycle> {
+        self.cycle
+    }
+
+    /// Whether the network is unlikely to be useful: every neuron is either silent or saturated,
+    /// or the state repeated (which, without inputs, means it will keep repeating).
+    pub fn is_degenerate(&self) -> bool {
+        self.cycle.is_some() || self.fraction(|count| count == 0 || count == self.ticks) == 1.0
+    }
+
+    fn fraction<F: Fn(u64) -> bool>(&self, f: F) -> f64 {
+        let matching = self.fire_counts.iter().filter(|&&count| f(count)).count();
+        matching as f64 / self.fire_counts.len().max(1) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{Effect, NetworkParams, NeuronValue, Plasticity};
+
+    #[test]
+    fn stats() {
+        // neuron 0 toggles itself, neuron 1 never fires
+        let mut net = Network::with_params(NetworkParams {
+            tresholds: vec![NeuronValue(0), NeuronValue(100)].into(),
+            effects: vec![Effect(0), Effect(-1), Effect(0), Effect(0)].into(),
+            input_neurons: vec![].into(),
+            output_neurons: vec![].into(),
+            plasticity: Plasticity::default(),
+            neuron_types: None,
+            connection_mask: None,
+        }).unwrap();
+
+        let mut stats = NetworkStats::new(2, 4);
+        stats.record(&net);
+        for _ in 0..5 {
+            stats.tick(&mut net);
+        }
+
+        assert_eq!(stats.ticks(), 6);
+        assert_eq!(stats.fire_counts(), &[3, 0]);
+        assert_eq!(stats.silent_fraction(), 0.5);
+        assert_eq!(stats.saturated_fraction(), 0.0);
+        assert_eq!(stats.accumulator_min(), Some(-1));
+        assert_eq!(stats.accumulator_max(), Some(0));
+        assert_eq!(stats.accumulator_mean(), -3.0 / 12.0);
+        assert_eq!(stats.cycle(), Some(Cycle { transient: 0, period: 2 }));
+        assert!(stats.is_degenerate());
+
+        // a period longer than the maximum is not detected
+        let mut stats = NetworkStats::new(2, 1);
+        for _ in 0..5 {
+            stats.tick(&mut net);
+        }
+        assert_eq!(stats.cycle(), None);
+    }
+}
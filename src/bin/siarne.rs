@@ -0,0 +1,309 @@
+//! Command line interface for creating, inspecting, running and mutating networks.
+
+use siarne::{
+    network::{Effect, NetworkParams, NeuronValue},
+    train::evolve::{apply_parameter_noise, build_network_from_noise},
+    Network,
+};
+
+use std::{
+    env,
+    fmt::Display,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    iter,
+    process,
+    str::FromStr,
+    time::Instant,
+};
+
+const USAGE: &str = "\
+usage: siarne <command> [options]
+
+commands:
+  new <params> --neurons <n> --connections <n> [--inputs <n>] [--outputs <n>] [--seed <n>]
+      create a params file with random parameters
+  inspect <params>
+      print the shape, effect and treshold histograms and IO mapping
+  run <params> [--input <csv>] [--ticks <n>]
+      apply every row of the input CSV (default stdin) to the input neurons, tick
+      the network and write the output neurons as CSV after every tick,
+      empty rows are skipped unless the network has no input neurons
+  mutate <params> --seed <n> --power <n> [--output <params>]
+      apply a noise pass, writing the result to the output (default in place)
+  prune <params> --magnitude <n> [--output <params>]
+      disable connections with a smaller effect magnitude, writing the result
+      to the output (default in place)
+  bench --neurons <n> --connections <n> [--ticks <n>]
+      measure ticks per second of a random network";
+
+type CliResult = Result<(), String>;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        Some("new") => new(Args::parse(&args[1..])),
+        Some("inspect") => inspect(Args::parse(&args[1..])),
+        Some("run") => run(Args::parse(&args[1..])),
+        Some("mutate") => mutate(Args::parse(&args[1..])),
+        Some("prune") => prune(Args::parse(&args[1..])),
+        Some("bench") => bench(Args::parse(&args[1..])),
+        Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(USAGE.to_owned()),
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+/// Positional arguments and `--name value` options.
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut positional = vec![];
+        let mut options = vec![];
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    let value = args.next().ok_or_else(|| format!("missing value for --{}", name))?;
+                    options.push((name.to_owned(), value.clone()));
+                }
+                None => positional.push(arg.clone()),
+            }
+        }
+
+        Ok(Self { positional, options })
+    }
+
+    fn path(&self) -> Result<&str, String> {
+        match self.positional.as_slice() {
+            [path] => Ok(path),
+            _ => Err(format!("expected a single params path\n\n{}", USAGE)),
+        }
+    }
+
+    fn get<T>(&self, name: &str) -> Result<Option<T>, String>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.options
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.parse().map_err(|e| format!("invalid --{}: {}", name, e)))
+            .transpose()
+    }
+
+    fn require<T>(&self, name: &str) -> Result<T, String>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.get(name)?.ok_or_else(|| format!("missing --{}\n\n{}", name, USAGE))
+    }
+}
+
+fn load(path: &str) -> Result<NetworkParams, String> {
+    NetworkParams::load(path).map_err(|e| format!("failed to read {}: {}", path, e))
+}
+
+/// Check the parameters like [Network::with_params], since loading does not.
+fn validate(params: &NetworkParams) -> CliResult {
+    Network::with_params(params.clone())
+        .map(drop)
+        .map_err(|e| format!("invalid params: {:?}", e))
+}
+
+fn save(params: &NetworkParams, path: &str) -> CliResult {
+    params.save(path).map_err(|e| format!("failed to write {}: {}", path, e))
+}
+
+fn new(args: Result<Args, String>) -> CliResult {
+    let args = args?;
+    let path = args.path()?;
+
+    let net = build_network_from_noise(
+        args.require("neurons")?,
+        args.require("connections")?,
+        args.get("inputs")?.unwrap_or(0),
+        args.get("outputs")?.unwrap_or(0),
+        args.get("seed")?.unwrap_or(0),
+        iter::empty(),
+    )
+    .map_err(|e| format!("invalid shape: {:?}", e))?;
+
+    save(net.params(), path)
+}
+
+fn inspect(args: Result<Args, String>) -> CliResult {
+    const BUCKETS: i64 = 16;
+
+    let params = load(args?.path()?)?;
+    let neuron_count = params.tresholds.len();
+    let connection_count = params.effects.len().checked_div(neuron_count).unwrap_or(0);
+
+    println!("neurons:     {}", neuron_count);
+    println!("connections: {}", connection_count);
+    println!("inputs:      {}", params.input_neurons.len());
+    println!("outputs:     {}", params.output_neurons.len());
+    println!("plasticity:  {:?}", params.plasticity);
+    match validate(&params) {
+        // the fireable fraction needs a valid shape
+        Ok(()) => println!("fireable:    {:.3}", params.fireable_fraction()),
+        Err(e) => println!("{}", e),
+    }
+    println!("sparsity:    {:.3}", params.sparsity().fraction());
+
+    let print_histogram = |name: &str, values: &mut dyn Iterator<Item = i64>, min: i64, max: i64| {
+        let width = (max - min + 1) / BUCKETS;
+        let mut counts = [0usize; BUCKETS as usize];
+        for value in values {
+            counts[((value - min) / width) as usize] += 1;
+        }
+
+        println!("\n{} histogram:", name);
+        for (i, count) in counts.iter().enumerate() {
+            let start = min + i as i64 * width;
+            println!("  {:>12} ..= {:>12}: {}", start, start + width - 1, count);
+        }
+    };
+    print_histogram(
+        "effect",
+        &mut params.effects.iter().map(|&Effect(e)| e as i64),
+        i8::MIN as i64,
+        i8::MAX as i64,
+    );
+    print_histogram(
+        "treshold",
+        &mut params.tresholds.iter().map(|&NeuronValue(t)| t as i64),
+        i32::MIN as i64,
+        i32::MAX as i64,
+    );
+
+    println!("\ninput neurons:");
+    for (i, neuron) in params.input_neurons.iter().enumerate() {
+        println!("  {:>6} -> {}", i, neuron);
+    }
+    println!("\noutput neurons:");
+    for (i, neuron) in params.output_neurons.iter().enumerate() {
+        println!("  {:>6} <- {}", i, neuron);
+    }
+
+    Ok(())
+}
+
+fn run(args: Result<Args, String>) -> CliResult {
+    let args = args?;
+    let mut net = Network::with_params(load(args.path()?)?)
+        .map_err(|e| format!("invalid params: {:?}", e))?;
+    let ticks: usize = args.get("ticks")?.unwrap_or(1);
+
+    let input: Box<dyn BufRead> = match args.get::<String>("input")? {
+        Some(path) => Box::new(BufReader::new(
+            File::open(&path).map_err(|e| format!("failed to open {}: {}", path, e))?,
+        )),
+        None => Box::new(BufReader::new(io::stdin())),
+    };
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut inputs = vec![];
+    let mut outputs = vec![NeuronValue(0); net.params().output_neurons.len()];
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.map_err(|e| format!("failed to read input: {}", e))?;
+        let input_count = net.params().input_neurons.len();
+        // without inputs an empty line is an empty input row, otherwise it is skipped
+        if line.trim().is_empty() && input_count > 0 {
+            continue;
+        }
+
+        inputs.clear();
+        for value in line.split(',').filter(|_| !line.trim().is_empty()) {
+            let value = value
+                .trim()
+                .parse()
+                .map_err(|e| format!("line {}: invalid input {:?}: {}", i + 1, value, e))?;
+            inputs.push(NeuronValue(value));
+        }
+        if inputs.len() != input_count {
+            return Err(format!(
+                "line {}: expected {} inputs, got {}",
+                i + 1,
+                input_count,
+                inputs.len(),
+            ));
+        }
+
+        net.apply_inputs(&inputs);
+        for _ in 0..ticks {
+            net.tick();
+            net.read_outputs(&mut outputs);
+
+            let row: Vec<String> = outputs.iter().map(|o| o.0.to_string()).collect();
+            writeln!(out, "{}", row.join(",")).map_err(|e| e.to_string())?;
+        }
+    }
+
+    out.flush().map_err(|e| e.to_string())
+}
+
+fn mutate(args: Result<Args, String>) -> CliResult {
+    let args = args?;
+    let path = args.path()?;
+    let mut params = load(path)?;
+    validate(&params)?;
+
+    apply_parameter_noise(&mut params, args.require("seed")?, args.require("power")?);
+
+    let output: Option<String> = args.get("output")?;
+    save(&params, output.as_deref().unwrap_or(path))
+}
+
+fn prune(args: Result<Args, String>) -> CliResult {
+    let args = args?;
+    let path = args.path()?;
+    let mut params = load(path)?;
+    validate(&params)?;
+
+    let sparsity = params.prune(args.require("magnitude")?);
+    println!("{} of {} connections disabled", sparsity.disabled, sparsity.total);
+
+    let output: Option<String> = args.get("output")?;
+    save(&params, output.as_deref().unwrap_or(path))
+}
+
+fn bench(args: Result<Args, String>) -> CliResult {
+    let args = args?;
+    let ticks: u32 = args.get("ticks")?.unwrap_or(1000);
+    let mut net = Network::new(args.require("neurons")?, args.require("connections")?, 0, 0)
+        .map_err(|e| format!("invalid shape: {:?}", e))?;
+
+    let start = Instant::now();
+    for _ in 0..ticks {
+        net.tick();
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{} ticks in {:.3?}, {:.1} ticks per second",
+        ticks,
+        elapsed,
+        ticks as f64 / elapsed.as_secs_f64(),
+    );
+
+    Ok(())
+}
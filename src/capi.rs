@@ -0,0 +1,283 @@
+//! C API, declared in `include/siarne.h` which is generated from this file by cbindgen.
+//! Networks are passed around as opaque pointers that must be freed with [siarne_network_free].
+//! Functions that can fail return a [SiarneError], with [SiarneError::Ok] on success.
+
+use crate::{
+    network::{self, Effect, NetworkParams, NeuronValue, Plasticity},
+    train::evolve::build_network_from_noise,
+    Network,
+};
+
+use std::{ffi::CStr, iter, os::raw::c_char, slice};
+
+/// Error codes of the C API, the first variants correspond to [network::Error].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SiarneError {
+    Ok = 0,
+    ZeroNeurons = 1,
+    ZeroConnections = 2,
+    TooManyConnections = 3,
+    EffectCountOverflow = 4,
+    InvalidNeuronIndex = 5,
+    InvalidNeuronTypes = 6,
+    EffectSign = 7,
+    InvalidConnectionMask = 8,
+    DisabledEffect = 9,
+    /// A null pointer was passed where it is not allowed.
+    Null = 100,
+    /// A buffer length does not match the network.
+    InvalidLength = 101,
+    /// A path is not valid UTF-8.
+    InvalidPath = 102,
+    /// Reading or writing a file failed.
+    Io = 103,
+}
+
+impl From<network::Error> for SiarneError {
+    fn from(e: network::Error) -> Self {
+        match e {
+            network::Error::ZeroNeurons => Self::ZeroNeurons,
+            network::Error::ZeroConnections => Self::ZeroConnections,
+            network::Error::TooManyConnections => Self::TooManyConnections,
+            network::Error::EffectCountOverflow => Self::EffectCountOverflow,
+            network::Error::InvalidNeuronIndex => Self::InvalidNeuronIndex,
+            network::Error::InvalidNeuronTypes => Self::InvalidNeuronTypes,
+            network::Error::EffectSign => Self::EffectSign,
+            network::Error::InvalidConnectionMask => Self::InvalidConnectionMask,
+            network::Error::DisabledEffect => Self::DisabledEffect,
+        }
+    }
+}
+
+/// Store a network in `out`.
+unsafe fn finish(result: Result<Network, network::Error>, out: *mut *mut Network) -> SiarneError {
+    match result {
+        Ok(net) => {
+            *out = Box::into_raw(Box::new(net));
+            SiarneError::Ok
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// A slice from a pointer that may be null when `len` is 0.
+unsafe fn slice_or_empty<'a, T>(data: *const T, len: usize) -> Option<&'a [T]> {
+    if len == 0 {
+        Some(&[])
+    } else if data.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(data, len))
+    }
+}
+
+unsafe fn path<'a>(path: *const c_char) -> Result<&'a str, SiarneError> {
+    if path.is_null() {
+        return Err(SiarneError::Null);
+    }
+    CStr::from_ptr(path).to_str().map_err(|_| SiarneError::InvalidPath)
+}
+
+/// Create a network with parameters generated from `seed`, see [build_network_from_noise].
+/// # Safety
+/// `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn siarne_network_new(
+    neuron_count: usize,
+    connection_count: usize,
+    input_count: usize,
+    output_count: usize,
+    seed: u64,
+    out: *mut *mut Network,
+) -> SiarneError {
+    if out.is_null() {
+        return SiarneError::Null;
+    }
+
+    let result = build_network_from_noise(
+        neuron_count,
+        connection_count,
+        input_count,
+        output_count,
+        seed,
+        iter::empty(),
+    );
+    finish(result, out)
+}
+
+/// Create a network from the specified parameters, see [Network::with_params].
+/// # Safety
+/// Every pointer must be valid for reads of its length, `out` must be valid for writes.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn siarne_network_from_params(
+    tresholds: *const i32,
+    neuron_count: usize,
+    effects: *const i8,
+    effect_count: usize,
+    input_neurons: *const usize,
+    input_count: usize,
+    output_neurons: *const usize,
+    output_count: usize,
+    out: *mut *mut Network,
+) -> SiarneError {
+    let slices = (
+        slice_or_empty(tresholds, neuron_count),
+        slice_or_empty(effects, effect_count),
+        slice_or_empty(input_neurons, input_count),
+        slice_or_empty(output_neurons, output_count),
+    );
+    let (tresholds, effects, input_neurons, output_neurons) = match slices {
+        (Some(t), Some(e), Some(i), Some(o)) if !out.is_null() => (t, e, i, o),
+        _ => return SiarneError::Null,
+    };
+
+    let params = NetworkParams {
+        tresholds: tresholds.iter().map(|&t| NeuronValue(t)).collect(),
+        effects: effects.iter().map(|&e| Effect(e)).collect(),
+        input_neurons: input_neurons.into(),
+        output_neurons: output_neurons.into(),
+        plasticity: Plasticity::default(),
+        neuron_types: None,
+        connection_mask: None,
+    };
+    finish(Network::with_params(params), out)
+}
+
+/// Load a network from a params file, see [NetworkParams::load].
+/// # Safety
+/// `path` must be a null terminated string, `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn siarne_network_load(path_str: *const c_char, out: *mut *mut Network) -> SiarneError {
+    if out.is_null() {
+        return SiarneError::Null;
+    }
+    let params = match path(path_str).map(NetworkParams::load) {
+        Ok(Ok(params)) => params,
+        Ok(Err(_)) => return SiarneError::Io,
+        Err(e) => return e,
+    };
+    finish(Network::with_params(params), out)
+}
+
+/// Save the parameters of a network to a file, see [NetworkParams::save].
+/// # Safety
+/// `net` must be a network created by this API, `path` must be a null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn siarne_network_save(net: *const Network, path_str: *const c_char) -> SiarneError {
+    let net = match net.as_ref() {
+        Some(net) => net,
+        None => return SiarneError::Null,
+    };
+    match path(path_str).map(|path| net.params().save(path)) {
+        Ok(Ok(())) => SiarneError::Ok,
+        Ok(Err(_)) => SiarneError::Io,
+        Err(e) => e,
+    }
+}
+
+/// Free a network, does nothing when `net` is null.
+/// # Safety
+/// `net` must be null or a network created by this API that is not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn siarne_network_free(net: *mut Network) {
+    if !net.is_null() {
+        drop(Box::from_raw(net));
+    }
+}
+
+/// The amount of neurons of a network.
+/// # Safety
+/// `net` must be a network created by this API.
+#[no_mangle]
+pub unsafe extern "C" fn siarne_network_neuron_count(net: *const Network) -> usize {
+    (*net).params().tresholds.len()
+}
+
+/// The amount of input neurons of a network.
+/// # Safety
+/// `net` must be a network created by this API.
+#[no_mangle]
+pub unsafe extern "C" fn siarne_network_input_count(net: *const Network) -> usize {
+    (*net).params().input_neurons.len()
+}
+
+/// The amount of output neurons of a network.
+/// # Safety
+/// `net` must be a network created by this API.
+#[no_mangle]
+pub unsafe extern "C" fn siarne_network_output_count(net: *const Network) -> usize {
+    (*net).params().output_neurons.len()
+}
+
+/// Apply inputs to the input neurons, see [Network::apply_inputs].
+/// # Safety
+/// `net` must be a network created by this API, `inputs` must be valid for reads of `len` values.
+#[no_mangle]
+pub unsafe extern "C" fn siarne_network_apply_inputs(net: *mut Network, inputs: *const i32, len: usize) -> SiarneError {
+    let (net, inputs) = match (net.as_mut(), slice_or_empty(inputs as *const NeuronValue, len)) {
+        (Some(net), Some(inputs)) => (net, inputs),
+        _ => return SiarneError::Null,
+    };
+    if inputs.len() != net.params().input_neurons.len() {
+        return SiarneError::InvalidLength;
+    }
+
+    net.apply_inputs(inputs);
+    SiarneError::Ok
+}
+
+/// Execute a tick, see [Network::tick].
+/// # Safety
+/// `net` must be a network created by this API.
+#[no_mangle]
+pub unsafe extern "C" fn siarne_network_tick(net: *mut Network) {
+    (*net).tick();
+}
+
+/// Read the values of the output neurons, see [Network::read_outputs].
+/// # Safety
+/// `net` must be a network created by this API, `outputs` must be valid for writes of `len` values.
+#[no_mangle]
+pub unsafe extern "C" fn siarne_network_read_outputs(net: *const Network, outputs: *mut i32, len: usize) -> SiarneError {
+    let net = match net.as_ref() {
+        Some(net) => net,
+        None => return SiarneError::Null,
+    };
+    if len != net.params().output_neurons.len() {
+        return SiarneError::InvalidLength;
+    }
+
+    let outputs = if len == 0 {
+        &mut []
+    } else if outputs.is_null() {
+        return SiarneError::Null;
+    } else {
+        slice::from_raw_parts_mut(outputs as *mut NeuronValue, len)
+    };
+    net.read_outputs(outputs);
+    SiarneError::Ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::ptr;
+
+    #[test]
+    fn errors() {
+        unsafe {
+            let mut net = ptr::null_mut();
+            assert_eq!(siarne_network_new(1, 2, 0, 0, 0, &mut net), SiarneError::TooManyConnections);
+            assert_eq!(siarne_network_new(0, 0, 0, 0, 0, &mut net), SiarneError::ZeroNeurons);
+            assert!(net.is_null());
+
+            assert_eq!(siarne_network_new(4, 2, 1, 1, 0, &mut net), SiarneError::Ok);
+            assert_eq!(siarne_network_apply_inputs(net, [0, 0].as_ptr(), 2), SiarneError::InvalidLength);
+            assert_eq!(siarne_network_read_outputs(net, ptr::null_mut(), 1), SiarneError::Null);
+            siarne_network_free(net);
+        }
+    }
+}
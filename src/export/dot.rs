@@ -0,0 +1,104 @@
+//! Graphviz DOT graphs of network connectivity.
+
+use crate::network::{connection_target, NetworkParams};
+
+use std::fmt::Write;
+
+impl NetworkParams {
+    /// A Graphviz DOT graph with a node per neuron and an edge for every non-zero effect,
+    /// see [NetworkParams::to_dot_pruned].
+    pub fn to_dot(&self) -> String {
+        self.to_dot_pruned(0)
+    }
+
+    /// A Graphviz DOT graph with a node per neuron, labelled with its index and treshold,
+    /// and an edge for every non-zero effect with a magnitude of at least `min_magnitude`.
+    /// Edges are labelled with the effect, positive effects are green and negative effects red.
+    /// Input neurons are drawn as boxes and output neurons are filled, both are annotated
+    /// with their index in `input_neurons` and `output_neurons`.
+    pub fn to_dot_pruned(&self, min_magnitude: u8) -> String {
+        let neuron_count = self.tresholds.len();
+        let connection_count = self.effects.len().checked_div(neuron_count).unwrap_or(0);
+
+        let mut dot = String::new();
+        dot.push_str("digraph network {\n");
+
+        for (neuron, treshold) in self.tresholds.iter().enumerate() {
+            let inputs = io_indices(&self.input_neurons, neuron, "in");
+            let outputs = io_indices(&self.output_neurons, neuron, "out");
+
+            let _ = write!(dot, "    {} [label=\"{}\\nt={}", neuron, neuron, treshold.0);
+            for label in inputs.iter().chain(outputs.iter()) {
+                let _ = write!(dot, "\\n{}", label);
+            }
+            dot.push('"');
+            dot.push_str(if inputs.is_empty() { ", shape=ellipse" } else { ", shape=box" });
+            if !outputs.is_empty() {
+                dot.push_str(", style=filled, fillcolor=lightgray");
+            }
+            dot.push_str("];\n");
+        }
+
+        for (i, effect) in self.effects.iter().enumerate() {
+            if effect.0 == 0 || (effect.0 as i32).unsigned_abs() < min_magnitude as u32 {
+                continue;
+            }
+
+            let src = i / connection_count;
+            let dst = connection_target(src, i % connection_count, neuron_count, connection_count);
+            let color = if effect.0 > 0 { "forestgreen" } else { "red" };
+            let _ = writeln!(dot, "    {} -> {} [label=\"{}\", color={}];", src, dst, effect.0, color);
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Labels for every position of `neuron` in `io_neurons`.
+fn io_indices(io_neurons: &[usize], neuron: usize, prefix: &str) -> Vec<String> {
+    io_neurons
+        .iter()
+        .enumerate()
+        .filter(|&(_, &n)| n == neuron)
+        .map(|(i, _)| format!("{}{}", prefix, i))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::network::{Effect, NetworkParams, NeuronValue, Plasticity};
+
+    #[test]
+    fn dot() {
+        let params = NetworkParams {
+            tresholds: vec![NeuronValue(0), NeuronValue(5), NeuronValue(-1)].into(),
+            effects: vec![
+                Effect(0), Effect(-3), Effect(1),
+                Effect(7), Effect(0), Effect(0),
+                Effect(0), Effect(0), Effect(0),
+            ].into(),
+            input_neurons: vec![2].into(),
+            output_neurons: vec![0, 2].into(),
+            plasticity: Plasticity::default(),
+            neuron_types: None,
+            connection_mask: None,
+        };
+
+        assert_eq!(
+            params.to_dot(),
+            "digraph network {\n\
+             \x20   0 [label=\"0\\nt=0\\nout0\", shape=ellipse, style=filled, fillcolor=lightgray];\n\
+             \x20   1 [label=\"1\\nt=5\", shape=ellipse];\n\
+             \x20   2 [label=\"2\\nt=-1\\nin0\\nout1\", shape=box, style=filled, fillcolor=lightgray];\n\
+             \x20   0 -> 0 [label=\"-3\", color=red];\n\
+             \x20   0 -> 1 [label=\"1\", color=forestgreen];\n\
+             \x20   1 -> 0 [label=\"7\", color=forestgreen];\n\
+             }\n",
+        );
+
+        let pruned = params.to_dot_pruned(3);
+        assert!(!pruned.contains("0 -> 1"));
+        assert!(pruned.contains("0 -> 0"));
+    }
+}
@@ -0,0 +1,173 @@
+//! Images of network activity and parameters, written as PGM/PPM or PNG (with the `png` feature).
+
+use crate::network::{Network, NetworkParams};
+
+use std::io::{self, Write};
+
+/// An 8 bit grayscale or RGB image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    /// 1 for grayscale, 3 for RGB.
+    pub channels: usize,
+    /// Rows of pixels from top to bottom.
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Write the image as binary PGM (grayscale) or PPM (RGB).
+    pub fn write_pnm<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let magic = if self.channels == 1 { "P5" } else { "P6" };
+        write!(w, "{}\n{} {}\n255\n", magic, self.width, self.height)?;
+        w.write_all(&self.pixels)
+    }
+
+    /// Write the image as PNG.
+    #[cfg(feature = "png")]
+    pub fn write_png<W: Write>(&self, w: W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(w, self.width as u32, self.height as u32);
+        encoder.set_color(if self.channels == 1 { png::ColorType::Grayscale } else { png::ColorType::Rgb });
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer.write_image_data(&self.pixels).map_err(io::Error::other)?;
+        writer.finish().map_err(io::Error::other)
+    }
+}
+
+/// Records which neurons fire over ticks, to be shown as a raster plot.
+pub struct Raster {
+    neuron_count: usize,
+    tick_count: usize,
+    /// Whether each neuron fires, per tick.
+    firing: Vec<bool>,
+}
+
+impl Raster {
+    pub fn new(neuron_count: usize) -> Self {
+        Self {
+            neuron_count,
+            tick_count: 0,
+            firing: vec![],
+        }
+    }
+
+    /// Record which neurons of `net` fire in the next tick, see [Network::firing].
+    /// # Panics
+    /// When `net` has a different amount of neurons than this raster.
+    pub fn record(&mut self, net: &Network) {
+        assert_eq!(net.params().tresholds.len(), self.neuron_count);
+
+        self.firing.extend(net.firing());
+        self.tick_count += 1;
+    }
+
+    /// Execute a tick on the network and record the result.
+    pub fn tick(&mut self, net: &mut Network) {
+        net.tick();
+        self.record(net);
+    }
+
+    /// A grayscale image with a column per recorded tick and a row per neuron,
+    /// where firing neurons are black.
+    pub fn image(&self) -> Image {
+        let mut pixels = vec![0; self.tick_count * self.neuron_count];
+        for (tick, firing) in self.firing.chunks(self.neuron_count.max(1)).enumerate() {
+            for (neuron, &fired) in firing.iter().enumerate() {
+                pixels[neuron * self.tick_count + tick] = if fired { 0 } else { 255 };
+            }
+        }
+
+        Image {
+            width: self.tick_count,
+            height: self.neuron_count,
+            channels: 1,
+            pixels,
+        }
+    }
+}
+
+/// An RGB heat map of the effects, with a row per neuron and a column per connection
+/// in the layout of [NetworkParams::effects].
+/// Positive effects are red, negative effects blue and zero effects white.
+pub fn effects_image(params: &NetworkParams) -> Image {
+    let neuron_count = params.tresholds.len();
+    let connection_count = params.effects.len().checked_div(neuron_count).unwrap_or(0);
+
+    let mut pixels = Vec::with_capacity(params.effects.len() * 3);
+    for effect in params.effects.iter() {
+        let magnitude = (effect.0 as i32).unsigned_abs().min(127) * 2;
+        let faded = (255 - magnitude) as u8;
+        if effect.0 >= 0 {
+            pixels.extend_from_slice(&[255, faded, faded]);
+        } else {
+            pixels.extend_from_slice(&[faded, faded, 255]);
+        }
+    }
+
+    Image {
+        width: connection_count,
+        height: neuron_count,
+        channels: 3,
+        pixels,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{Effect, NeuronValue, Plasticity};
+
+    #[test]
+    fn raster_and_effects() {
+        let mut net = Network::with_params(NetworkParams {
+            tresholds: vec![NeuronValue(0), NeuronValue(5)].into(),
+            effects: vec![Effect(0), Effect(-3), Effect(127), Effect(-128)].into(),
+            input_neurons: vec![].into(),
+            output_neurons: vec![].into(),
+            plasticity: Plasticity::default(),
+            neuron_types: None,
+            connection_mask: None,
+        }).unwrap();
+
+        let mut raster = Raster::new(2);
+        raster.record(&net);
+        raster.tick(&mut net);
+        raster.tick(&mut net);
+
+        let image = raster.image();
+        assert_eq!((image.width, image.height), (3, 2));
+        assert_eq!(image.pixels, [0, 255, 0, 255, 255, 255]);
+
+        let mut pgm = vec![];
+        image.write_pnm(&mut pgm).unwrap();
+        assert_eq!(&pgm[..11], b"P5\n3 2\n255\n");
+        assert_eq!(pgm.len(), 11 + 6);
+
+        let image = effects_image(net.params());
+        assert_eq!((image.width, image.height, image.channels), (2, 2, 3));
+        assert_eq!(
+            image.pixels,
+            [
+                255, 255, 255, 249, 249, 255,
+                255, 1, 1, 1, 1, 255,
+            ],
+        );
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn png() {
+        let image = Image {
+            width: 2,
+            height: 1,
+            channels: 1,
+            pixels: vec![0, 255],
+        };
+
+        let mut png = vec![];
+        image.write_png(&mut png).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+}
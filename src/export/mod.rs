@@ -0,0 +1,6 @@
+//! Code related to writing the parameters and activity of [Network](crate::Network)s
+//! in formats understood by other tools.
+
+pub mod dot;
+pub mod image;
+pub mod vcd;
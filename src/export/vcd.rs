@@ -0,0 +1,148 @@
+//! Recording the activity of neurons as a Value Change Dump, viewable in waveform viewers like GTKWave.
+
+use crate::network::{Network, NeuronValue};
+
+use std::io::{self, Write};
+
+/// Records selected neurons of a [Network] over ticks in the Value Change Dump format.  
+/// Every neuron has a 1 bit signal `fired` telling whether it fires in the next tick
+/// (see [Network::firing]) and a 32 bit signal `accumulator` with its input.
+/// Every tick is one time unit. Networks that are not traced are not slowed down in any way.
+pub struct VcdTracer<W: Write> {
+    out: W,
+    neurons: Box<[usize]>,
+    time: u64,
+    last_values: Vec<(bool, NeuronValue)>,
+}
+
+impl<W: Write> VcdTracer<W> {
+    /// Write the header and the current state of `neurons` to `out`.
+    /// # Panics
+    /// When `neurons` contains an out of bounds index.
+    pub fn new(mut out: W, net: &Network, neurons: &[usize]) -> io::Result<Self> {
+        let neuron_count = net.params().tresholds.len();
+        assert!(neurons.iter().all(|&n| n < neuron_count));
+
+        writeln!(out, "$version siarne {} $end", env!("CARGO_PKG_VERSION"))?;
+        writeln!(out, "$timescale 1 ns $end")?;
+        writeln!(out, "$scope module network $end")?;
+        for (i, neuron) in neurons.iter().enumerate() {
+            writeln!(out, "$scope module neuron_{} $end", neuron)?;
+            writeln!(out, "$var wire 1 {} fired $end", identifier(2 * i))?;
+            writeln!(out, "$var integer 32 {} accumulator $end", identifier(2 * i + 1))?;
+            writeln!(out, "$upscope $end")?;
+        }
+        writeln!(out, "$upscope $end")?;
+        writeln!(out, "$enddefinitions $end")?;
+
+        let mut tracer = Self {
+            out,
+            neurons: neurons.into(),
+            time: 0,
+            last_values: vec![],
+        };
+        writeln!(tracer.out, "#0")?;
+        writeln!(tracer.out, "$dumpvars")?;
+        tracer.write_changes(net)?;
+        writeln!(tracer.out, "$end")?;
+
+        Ok(tracer)
+    }
+
+    /// Execute a tick on the network and record the new state of the traced neurons.
+    pub fn tick(&mut self, net: &mut Network) -> io::Result<()> {
+        net.tick();
+        self.time += 1;
+        writeln!(self.out, "#{}", self.time)?;
+        self.write_changes(net)
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn write_changes(&mut self, net: &Network) -> io::Result<()> {
+        let accumulators = net.accumulators();
+        let tresholds = &net.params().tresholds;
+        let first = self.last_values.is_empty();
+
+        for (i, &neuron) in self.neurons.iter().enumerate() {
+            let accumulator = accumulators[neuron];
+            let fired = accumulator >= tresholds[neuron];
+
+            if first {
+                self.last_values.push((!fired, NeuronValue(!accumulator.0)));
+            }
+            let last = &mut self.last_values[i];
+
+            if last.0 != fired {
+                writeln!(self.out, "{}{}", fired as u8, identifier(2 * i))?;
+            }
+            if last.1 != accumulator {
+                writeln!(self.out, "b{:b} {}", accumulator.0 as u32, identifier(2 * i + 1))?;
+            }
+            *last = (fired, accumulator);
+        }
+
+        Ok(())
+    }
+}
+
+/// A short signal identifier made of printable ASCII characters.
+fn identifier(mut index: usize) -> String {
+    const FIRST: u8 = b'!';
+    const COUNT: usize = (b'~' - b'!' + 1) as usize;
+
+    let mut id = String::new();
+    loop {
+        id.push((FIRST + (index % COUNT) as u8) as char);
+        index /= COUNT;
+        if index == 0 {
+            break id;
+        }
+        index -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{Effect, NetworkParams, Plasticity};
+
+    #[test]
+    fn trace() {
+        let mut net = Network::with_params(NetworkParams {
+            tresholds: vec![NeuronValue(0), NeuronValue(5)].into(),
+            effects: vec![Effect(0), Effect(-3), Effect(0), Effect(0)].into(),
+            input_neurons: vec![].into(),
+            output_neurons: vec![].into(),
+            plasticity: Plasticity::default(),
+            neuron_types: None,
+            connection_mask: None,
+        }).unwrap();
+
+        let mut tracer = VcdTracer::new(vec![], &net, &[0, 1]).unwrap();
+        tracer.tick(&mut net).unwrap();
+        tracer.tick(&mut net).unwrap();
+        let vcd = String::from_utf8(tracer.finish().unwrap()).unwrap();
+
+        let changes = vcd.split("$enddefinitions $end\n").nth(1).unwrap();
+        assert_eq!(
+            changes,
+            "#0\n$dumpvars\n1!\nb0 \"\n0#\nb0 $\n$end\n\
+             #1\n0!\nb11111111111111111111111111111101 \"\n\
+             #2\n1!\nb0 \"\n",
+        );
+        assert!(vcd.contains("$var integer 32 $ accumulator $end"));
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(identifier(0), "!");
+        assert_eq!(identifier(93), "~");
+        assert_eq!(identifier(94), "!!");
+        assert_ne!(identifier(94 + 94 * 94), identifier(94));
+    }
+}
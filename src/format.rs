@@ -0,0 +1,84 @@
+//! Little endian encoding helpers shared by the file formats of the crate.
+
+use std::{
+    convert::TryFrom,
+    io::{self, Read, Write},
+};
+
+pub(crate) fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub(crate) fn write_u8<W: Write>(w: &mut W, value: u8) -> io::Result<()> {
+    w.write_all(&[value])
+}
+
+pub(crate) fn write_u32<W: Write>(w: &mut W, value: u32) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+
+pub(crate) fn write_u64<W: Write>(w: &mut W, value: u64) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+
+pub(crate) fn write_f64<W: Write>(w: &mut W, value: f64) -> io::Result<()> {
+    write_u64(w, value.to_bits())
+}
+
+pub(crate) fn read_u8<R: Read>(r: &mut R) -> io::Result<u8> {
+    let mut buf = [0; 1];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+pub(crate) fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+pub(crate) fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+pub(crate) fn read_f64<R: Read>(r: &mut R) -> io::Result<f64> {
+    read_u64(r).map(f64::from_bits)
+}
+
+pub(crate) fn read_bool<R: Read>(r: &mut R) -> io::Result<bool> {
+    bool_from_u8(read_u8(r)?)
+}
+
+pub(crate) fn bool_from_u8(value: u8) -> io::Result<bool> {
+    match value {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(invalid_data("invalid boolean")),
+    }
+}
+
+/// Read a length or index, failing when it does not fit in a [usize].
+pub(crate) fn read_usize<R: Read>(r: &mut R) -> io::Result<usize> {
+    let value = read_u64(r)?;
+    usize::try_from(value).map_err(|_| invalid_data("value does not fit in usize"))
+}
+
+pub(crate) fn write_magic<W: Write>(w: &mut W, magic: &[u8; 8], version: u32) -> io::Result<()> {
+    w.write_all(magic)?;
+    write_u32(w, version)
+}
+
+pub(crate) fn read_magic<R: Read>(r: &mut R, magic: &[u8; 8], version: u32) -> io::Result<()> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    if &buf != magic {
+        return Err(invalid_data("invalid magic"));
+    }
+    if read_u32(r)? != version {
+        return Err(invalid_data("unsupported version"));
+    }
+
+    Ok(())
+}
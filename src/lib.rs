@@ -1,6 +1,20 @@
 
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod format;
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "std")]
+pub mod export;
 pub mod network;
+#[cfg(feature = "python")]
+pub mod python;
 pub mod train;
 
 pub use network::Network;
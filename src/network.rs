@@ -1,11 +1,47 @@
 //! Code related to creating and executing [Network]s
 
-use rand::prelude::*;
-
-use std::{iter, ops::Range};
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+#[cfg(feature = "std")]
+use crate::format::*;
+
+use alloc::{boxed::Box, vec};
+use core::{iter, ops::Range, slice};
+#[cfg(feature = "std")]
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+mod borrowed;
+mod init;
+#[cfg(feature = "mmap")]
+mod mapped;
+mod prune;
+
+pub use borrowed::{NetworkRef, ParamsRef};
+pub use init::{EffectInit, InitParams, TresholdInit};
+pub use prune::Sparsity;
+#[cfg(feature = "mmap")]
+pub use mapped::MappedParams;
 
 const ACCUMULATOR_BUF_COUNT: usize = 2;
 
+#[cfg(feature = "std")]
+const PARAMS_MAGIC: &[u8; 8] = b"SIARNEP\0";
+#[cfg(feature = "std")]
+const PARAMS_VERSION: u32 = 1;
+/// The params file contains a neuron type section after the output neurons.
+#[cfg(feature = "std")]
+const PARAMS_FLAG_NEURON_TYPES: u32 = 1;
+/// The params file contains a connection mask section after the neuron types.
+#[cfg(feature = "std")]
+const PARAMS_FLAG_CONNECTION_MASK: u32 = 2;
+
 #[derive(Debug)]
 pub enum Error {
     ZeroNeurons,
@@ -13,19 +49,87 @@ pub enum Error {
     TooManyConnections,
     EffectCountOverflow,
     InvalidNeuronIndex,
+    /// The neuron types do not have an element for every neuron.
+    InvalidNeuronTypes,
+    /// An effect does not have the sign required by the type of its neuron.
+    EffectSign,
+    /// The connection mask does not have an element for every effect.
+    InvalidConnectionMask,
+    /// A disabled connection has an effect other than 0.
+    DisabledEffect,
 }
 
 /// A value related to the input of a neuron.
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Default)]
+#[repr(transparent)]
 pub struct NeuronValue(pub i32);
 
 /// The effect of a connection is the value added to the input of a neuron
 /// when the neuron at the other end of the connection fires. Connections
 /// are one-directional.
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Default)]
+#[repr(transparent)]
 pub struct Effect(pub i8);
 
-#[derive(Clone)]
+impl Effect {
+    /// View raw bytes as effects, e.g the effects section of a params file
+    /// included with `include_bytes!`.
+    pub fn from_bytes(bytes: &[u8]) -> &[Effect] {
+        // safety: Effect is a transparent wrapper of i8, which has the same layout as u8
+        unsafe { slice::from_raw_parts(bytes.as_ptr() as *const Effect, bytes.len()) }
+    }
+}
+
+/// The type of a neuron under Dale's law, which constrains the sign of all its effects,
+/// see [NetworkParams::neuron_types].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NeuronType {
+    /// Every effect is at least 0.
+    Excitatory,
+    /// Every effect is at most 0.
+    Inhibitory,
+}
+
+impl NeuronType {
+    /// Whether a neuron of this type can have `effect`.
+    pub fn allows(self, effect: Effect) -> bool {
+        match self {
+            Self::Excitatory => effect.0 >= 0,
+            Self::Inhibitory => effect.0 <= 0,
+        }
+    }
+
+    /// The closest effect to `effect` that this type allows.
+    pub fn clamp(self, effect: Effect) -> Effect {
+        match self {
+            Self::Excitatory => Effect(effect.0.max(0)),
+            Self::Inhibitory => Effect(effect.0.min(0)),
+        }
+    }
+}
+
+/// Parameters of the plasticity rule that changes effects during [Network::tick].  
+/// When a neuron fires one tick after a neuron with a connection to it, the effect of that
+/// connection is increased by `potentiation`. When a neuron fires one tick after a neuron it
+/// has a connection to, the effect of that connection is decreased by `depression`.
+/// Effects saturate instead of overflowing.  
+/// When `reward_modulated` is set, these changes are accumulated in an eligibility trace per connection
+/// instead, and only applied to the effects by [Network::apply_reward].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Plasticity {
+    /// When `false` effects never change during a tick, and the other parameters
+    /// are not changed by noise passes.
+    pub enabled: bool,
+    pub potentiation: u8,
+    pub depression: u8,
+    pub reward_modulated: bool,
+    /// Every tick each eligibility trace loses `1 / 2^trace_decay` of its value, rounded towards 0.
+    pub trace_decay: u8,
+    /// The change of an effect on reward is `trace * reward / 2^reward_shift`, rounded towards 0.
+    pub reward_shift: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct NetworkParams {
     pub tresholds: Box<[NeuronValue]>,
     /// This is a matrix with dimensions `connection_count` x `neuron_count`
@@ -35,13 +139,16 @@ pub struct NetworkParams {
     /// being arranged in a circle.
     /// # Examples
     /// ```
-    /// # use ans::network::{NetworkParams, NeuronValue, Effect};
+    /// # use siarne::network::{NetworkParams, NeuronValue, Effect, Plasticity};
     /// // create dummy params with 3 neurons and 3 connections per neuron
     /// let params = NetworkParams {
     ///     tresholds: vec![NeuronValue(0); 3].into(),
     ///     effects: vec![Effect(0); 3 * 3].into(),
     ///     input_neurons: vec![].into(),
     ///     output_neurons: vec![].into(),
+    ///     plasticity: Plasticity::default(),
+    ///     neuron_types: None,
+    ///     connection_mask: None,
     /// };
     /// 
     /// // print connection effects from neuron to neuron
@@ -60,75 +167,278 @@ pub struct NetworkParams {
     pub effects: Box<[Effect]>,
     pub input_neurons: Box<[usize]>,
     pub output_neurons: Box<[usize]>,
+    pub plasticity: Plasticity,
+    /// When set, the type of every neuron, constraining the sign of its row of effects.
+    /// Noise, crossover and plasticity keep effects within the constraint by clamping them.
+    pub neuron_types: Option<Box<[NeuronType]>>,
+    /// When set, whether each connection is enabled, in the same layout as `effects`.
+    /// A disabled connection must have an effect of 0, which noise and plasticity keep it at,
+    /// so ticks need no extra work to skip it. See [NetworkParams::prune].
+    pub connection_mask: Option<Box<[bool]>>,
 }
 
-/// A structure containing a collection of interconnected neurons.
-pub struct Network {
-    accumulators: [Option<Box<[NeuronValue]>>; ACCUMULATOR_BUF_COUNT],
-    current_cum_buf: usize,
-    connection_count: usize,
-    params: NetworkParams,
-}
+#[cfg(feature = "std")]
+impl NetworkParams {
+    /// Write the parameters in the binary format of this crate.  
+    /// The format starts with a header containing the amount of neurons, connections per neuron,
+    /// input and output neurons, all as little endian `u64`. The effects are stored last,
+    /// so they can be used in place by memory mapping.
+    /// Optional sections are indicated by a flag in the header.
+    /// # Panics
+    /// When the amount of effects is not a multiple of the amount of neurons,
+    /// or when there are neuron types or a connection mask with the wrong length.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let neuron_count = self.tresholds.len();
+        let connection_count = self.effects.len().checked_div(neuron_count).unwrap_or(0);
+        assert_eq!(neuron_count * connection_count, self.effects.len());
+
+        write_magic(w, PARAMS_MAGIC, PARAMS_VERSION)?;
+        let mut flags = 0;
+        if let Some(types) = &self.neuron_types {
+            assert_eq!(types.len(), neuron_count);
+            flags |= PARAMS_FLAG_NEURON_TYPES;
+        }
+        if let Some(mask) = &self.connection_mask {
+            assert_eq!(mask.len(), self.effects.len());
+            flags |= PARAMS_FLAG_CONNECTION_MASK;
+        }
+        write_u32(w, flags)?;
+        for &count in &[neuron_count, connection_count, self.input_neurons.len(), self.output_neurons.len()] {
+            write_u64(w, count as u64)?;
+        }
 
-impl Network {
-    /// Create a [Network] with randomly initialized parameters.  
-    /// `connection_count` is the amount of inputs per neuron.
-    /// # Errors
-    /// When `neuron_count` is 0, [Error::ZeroNeurons].
-    /// When `connection_count` is 0, [Error::ZeroConnections].
-    /// When `connection_count` > `neuron_count`, [Error::TooManyConnections].   
-    /// When the result of `neuron_count * connection_count` does not fit in a [usize], [Error::EffectCountOverflow].  
-    pub fn new(neuron_count: usize, connection_count: usize, input_count: usize, output_count: usize) -> Result<Self, Error> {
-        if neuron_count == 0 {
-            return Err(Error::ZeroNeurons);
+        let plasticity = &self.plasticity;
+        w.write_all(&[
+            plasticity.enabled as u8,
+            plasticity.potentiation,
+            plasticity.depression,
+            plasticity.reward_modulated as u8,
+            plasticity.trace_decay,
+            plasticity.reward_shift,
+        ])?;
+
+        for treshold in self.tresholds.iter() {
+            w.write_all(&treshold.0.to_le_bytes())?;
+        }
+        for &neuron in self.input_neurons.iter().chain(self.output_neurons.iter()) {
+            write_u64(w, neuron as u64)?;
         }
-        if connection_count == 0 {
-            return Err(Error::ZeroConnections);
+        if let Some(types) = &self.neuron_types {
+            let types: Vec<u8> = types.iter().map(|&t| (t == NeuronType::Inhibitory) as u8).collect();
+            w.write_all(&types)?;
         }
-        if connection_count > neuron_count {
-            return Err(Error::TooManyConnections);
+        if let Some(mask) = &self.connection_mask {
+            // packed 8 connections per byte, starting at the least significant bit
+            let mut bytes = vec![0u8; mask.len().div_ceil(8)];
+            for (i, _) in mask.iter().enumerate().filter(|(_, &enabled)| enabled) {
+                bytes[i / 8] |= 1 << (i % 8);
+            }
+            w.write_all(&bytes)?;
         }
 
-        let effect_count = neuron_count
-            .checked_mul(connection_count)
-            .ok_or(Error::EffectCountOverflow)?;
-        
-        let mut rng = thread_rng();
+        let effects: Vec<u8> = self.effects.iter().map(|e| e.0 as u8).collect();
+        w.write_all(&effects)
+    }
 
-        let tresholds = iter::repeat_with(|| NeuronValue(rng.gen()))
-            .take(neuron_count)
+    /// Read parameters written by [NetworkParams::write_to].  
+    /// The parameters are not validated, [Network::with_params] does that.
+    /// # Errors
+    /// Any error of `r`, or [io::ErrorKind::InvalidData] when the data is not in the expected format.
+    pub fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+        let (mut params, effect_count) = Self::read_without_effects(r)?;
+        params.effects = read_exact_vec(r, effect_count, 1)?
+            .into_iter()
+            .map(|b| Effect(b as i8))
             .collect();
 
-        let effects = iter::repeat_with(|| Effect(rng.gen()))
-            .take(effect_count)
-            .collect();
-        
-        let neuron_dist = rand::distributions::Uniform::from(0..neuron_count);
+        Ok(params)
+    }
 
-        let input_neurons = iter::repeat_with(|| neuron_dist.sample(&mut rng))
-            .take(input_count)
-            .collect();
+    /// Read everything before the effects, returning the parameters without effects
+    /// and the amount of effects that follow.
+    fn read_without_effects<R: Read>(r: &mut R) -> io::Result<(Self, usize)> {
+        let header = ParamsHeader::read_from(r)?;
+
+        let mut plasticity = [0; 6];
+        r.read_exact(&mut plasticity)?;
+        let plasticity = Plasticity {
+            enabled: bool_from_u8(plasticity[0])?,
+            potentiation: plasticity[1],
+            depression: plasticity[2],
+            reward_modulated: bool_from_u8(plasticity[3])?,
+            trace_decay: plasticity[4],
+            reward_shift: plasticity[5],
+        };
 
-        let output_neurons = iter::repeat_with(|| neuron_dist.sample(&mut rng))
-            .take(output_count)
+        let tresholds = read_exact_vec(r, header.neuron_count, 4)?
+            .chunks_exact(4)
+            .map(|b| NeuronValue(i32::from_le_bytes([b[0], b[1], b[2], b[3]])))
             .collect();
-            
-        let params = NetworkParams {
+
+        let input_neurons = iter::repeat_with(|| read_usize(r))
+            .take(header.input_count)
+            .collect::<io::Result<_>>()?;
+        let output_neurons = iter::repeat_with(|| read_usize(r))
+            .take(header.output_count)
+            .collect::<io::Result<_>>()?;
+
+        let neuron_types = if header.neuron_types {
+            let types = read_exact_vec(r, header.neuron_count, 1)?
+                .into_iter()
+                .map(|b| match bool_from_u8(b)? {
+                    false => Ok(NeuronType::Excitatory),
+                    true => Ok(NeuronType::Inhibitory),
+                })
+                .collect::<io::Result<_>>()?;
+            Some(types)
+        } else {
+            None
+        };
+
+        let connection_mask = if header.connection_mask {
+            let bytes = read_exact_vec(r, header.effect_count.div_ceil(8), 1)?;
+            let mask = (0..header.effect_count)
+                .map(|i| bytes[i / 8] & (1 << (i % 8)) != 0)
+                .collect();
+            Some(mask)
+        } else {
+            None
+        };
+
+        let params = Self {
             tresholds,
-            effects,
+            effects: Box::new([]),
             input_neurons,
             output_neurons,
+            plasticity,
+            neuron_types,
+            connection_mask,
         };
+        Ok((params, header.effect_count))
+    }
+
+    /// Write the parameters to a file, see [NetworkParams::write_to].
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write_to(&mut w)?;
+        w.flush()
+    }
+
+    /// Read parameters from a file, see [NetworkParams::read_from].
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(feature = "std")]
+struct ParamsHeader {
+    neuron_count: usize,
+    effect_count: usize,
+    input_count: usize,
+    output_count: usize,
+    neuron_types: bool,
+    connection_mask: bool,
+}
+
+#[cfg(feature = "std")]
+impl ParamsHeader {
+    fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+        read_magic(r, PARAMS_MAGIC, PARAMS_VERSION)?;
+        let flags = read_u32(r)?;
+        if flags & !(PARAMS_FLAG_NEURON_TYPES | PARAMS_FLAG_CONNECTION_MASK) != 0 {
+            return Err(invalid_data("unsupported flags"));
+        }
+
+        let neuron_count = read_usize(r)?;
+        let connection_count = read_usize(r)?;
+        let effect_count = neuron_count
+            .checked_mul(connection_count)
+            .ok_or_else(|| invalid_data("effect count overflow"))?;
 
-        let accumulator_buf: Box<[NeuronValue]> = vec![NeuronValue(0); neuron_count].into();
-        
         Ok(Self {
-            accumulators: [Some(accumulator_buf.clone()), Some(accumulator_buf)],
-            current_cum_buf: 0,
-            connection_count,
-            params            
+            neuron_count,
+            effect_count,
+            input_count: read_usize(r)?,
+            output_count: read_usize(r)?,
+            neuron_types: flags & PARAMS_FLAG_NEURON_TYPES != 0,
+            connection_mask: flags & PARAMS_FLAG_CONNECTION_MASK != 0,
         })
     }
+}
+
+/// Read `count` elements of `size` bytes, without trusting `count` for the allocation.
+#[cfg(feature = "std")]
+fn read_exact_vec<R: Read>(r: &mut R, count: usize, size: usize) -> io::Result<Vec<u8>> {
+    let len = count
+        .checked_mul(size)
+        .ok_or_else(|| invalid_data("length overflow"))?;
+
+    let mut buf = vec![];
+    r.take(len as u64).read_to_end(&mut buf)?;
+    if buf.len() != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(buf)
+}
+
+/// A structure containing a collection of interconnected neurons.
+pub struct Network {
+    accumulators: [Option<Box<[NeuronValue]>>; ACCUMULATOR_BUF_COUNT],
+    current_cum_buf: usize,
+    connection_count: usize,
+    params: NetworkParams,
+    /// Whether each neuron fired in the last tick, only used for plasticity.
+    last_firing: Option<Box<[bool]>>,
+    /// Accumulated effect changes per connection, only used for reward modulated plasticity.
+    eligibility: Option<Box<[i16]>>,
+}
+
+impl Network {
+    /// Create a [Network] with randomly initialized parameters.  
+    /// `connection_count` is the amount of inputs per neuron.  
+    /// The parameters are generated by [thread_rng], use [Network::from_seed] for reproducible networks.
+    /// # Errors
+    /// See [Network::from_seed].
+    #[cfg(feature = "std")]
+    pub fn new(neuron_count: usize, connection_count: usize, input_count: usize, output_count: usize) -> Result<Self, Error> {
+        let params = NetworkParams::random(
+            neuron_count,
+            connection_count,
+            input_count,
+            output_count,
+            &InitParams::default(),
+            &mut thread_rng(),
+        )?;
+
+        Self::with_params(params)
+    }
+
+    /// Create a [Network] with randomly initialized parameters generated from `seed`,
+    /// see [NetworkParams::random] for other distributions and random number generators.
+    /// # Errors
+    /// When `neuron_count` is 0, [Error::ZeroNeurons].
+    /// When `connection_count` is 0, [Error::ZeroConnections].
+    /// When `connection_count` > `neuron_count`, [Error::TooManyConnections].   
+    /// When the result of `neuron_count * connection_count` does not fit in a [usize], [Error::EffectCountOverflow].  
+    pub fn from_seed(
+        neuron_count: usize,
+        connection_count: usize,
+        input_count: usize,
+        output_count: usize,
+        seed: u64,
+    ) -> Result<Self, Error> {
+        let params = NetworkParams::random(
+            neuron_count,
+            connection_count,
+            input_count,
+            output_count,
+            &InitParams::default(),
+            &mut ChaCha8Rng::seed_from_u64(seed),
+        )?;
+
+        Self::with_params(params)
+    }
 
     /// Create a [Network] with the specified parameters.
     /// # Errors
@@ -136,35 +446,33 @@ impl Network {
     /// When the amount of effects per neuron is less than 1, [Error::ZeroConnections].    
     /// When there are more connections per neuron than neurons, [Error::TooManyConnections].  
     /// When `input_neurons` contains an out of bounds index, [Error::InvalidNeuronIndex].  
+    /// When `neuron_types` does not have an element for every neuron, [Error::InvalidNeuronTypes].  
+    /// When an effect does not have the sign of its neuron's type, [Error::EffectSign].  
+    /// When `connection_mask` does not have an element for every effect, [Error::InvalidConnectionMask].  
+    /// When a disabled connection has an effect other than 0, [Error::DisabledEffect].  
     pub fn with_params(params: NetworkParams) -> Result<Self, Error> {
+        let connection_count = validate((&params).into())?;
         let neuron_count = params.tresholds.len();
-        if neuron_count == 0 {
-            return Err(Error::ZeroNeurons);
-        }
-
-        let connection_count = params.effects.len() / neuron_count;
-        if connection_count == 0 {
-            return Err(Error::ZeroConnections);
-        }
-
-        if connection_count > neuron_count {
-            return Err(Error::TooManyConnections);
-        }
-
-        if !(
-            params.input_neurons.iter().copied().all(|i| i < neuron_count) &&
-            params.output_neurons.iter().copied().all(|i| i < neuron_count)
-        ) {
-            return Err(Error::InvalidNeuronIndex);
-        }
 
         let accumulator_buf: Box<[NeuronValue]> = vec![NeuronValue(0); neuron_count].into();
+        let last_firing = if params.plasticity.enabled {
+            Some(vec![false; neuron_count].into())
+        } else {
+            None
+        };
+        let eligibility = if params.plasticity.enabled && params.plasticity.reward_modulated {
+            Some(vec![0; params.effects.len()].into())
+        } else {
+            None
+        };
         
         Ok(Self {
             accumulators: [Some(accumulator_buf.clone()), Some(accumulator_buf)],
             current_cum_buf: 0,
             connection_count,
-            params    
+            params,
+            last_firing,
+            eligibility,
         })
     }
 
@@ -182,132 +490,168 @@ impl Network {
     /// # Panics
     /// When `inputs.len()` is not equal to the input neuron count.  
     pub fn apply_inputs(&mut self, inputs: &[NeuronValue]) {
-        assert_eq!(self.params.input_neurons.len(), inputs.len());
-
         let cum = self.accumulators[self.last_accumulator_buf_index()]
             .as_mut()
             .unwrap();
 
-        self.params.input_neurons
+        // safety: the input neurons are validated on construction
+        unsafe {
+            add_inputs(cum, &self.params.input_neurons, inputs);
+        }
+    }
+
+    /// The inputs of all neurons for the next tick, i.e the result of the last tick plus any applied inputs.
+    pub fn accumulators(&self) -> &[NeuronValue] {
+        self.last_accumulator_buf()
+    }
+
+    /// Replace the inputs of all neurons for the next tick, see [Network::accumulators].
+    /// # Panics
+    /// When `accumulators.len()` is not equal to the neuron count.
+    pub fn set_accumulators(&mut self, accumulators: &[NeuronValue]) {
+        let index = self.last_accumulator_buf_index();
+        self.accumulators[index]
+            .as_mut()
+            .unwrap()
+            .copy_from_slice(accumulators);
+    }
+
+    /// Whether each neuron's input is at or above its treshold, i.e whether it fires in the next tick.
+    pub fn firing(&self) -> impl Iterator<Item = bool> + '_ {
+        self.last_accumulator_buf()
             .iter()
-            .copied()
-            .zip(inputs.iter().copied())
-            .for_each(|(neuron, input)| {
-                unsafe {
-                    cum.get_unchecked_mut(neuron).0 += input.0;
-                }
-            });
+            .zip(self.params.tresholds.iter())
+            .map(|(input, treshold)| input >= treshold)
     }
 
     /// Read values of the designated output neurons into the specified buffer, in order.
     /// # Panics
     /// When `outputs.len()` is not equal to the output neuron count.  
     pub fn read_outputs(&self, outputs: &mut [NeuronValue]) {
-        assert_eq!(self.params.output_neurons.len(), outputs.len());
-
-        let cum = self.last_accumulator_buf();
-
-        self.params.output_neurons
-            .iter()
-            .copied()
-            .zip(outputs.iter_mut())
-            .for_each(|(neuron, output)| {
-                unsafe {
-                    *output = *cum.get_unchecked(neuron);
-                }
-            });
+        // safety: the output neurons are validated on construction
+        unsafe {
+            copy_outputs(self.last_accumulator_buf(), &self.params.output_neurons, outputs);
+        }
     }
 
     /// Execute a tick on the network, evaluating each neuron and applying effects to other neurons if it fires.  
     /// Only the result of the last tick is considered, i.e the effects are applied on a zeroed buffer,
-    /// but whether to apply an effect or not is determined by looking at the buffer from the last tick.
+    /// but whether to apply an effect or not is determined by looking at the buffer from the last tick.  
+    /// When [Plasticity] is enabled the effects are changed afterwards, see [NetworkParams::plasticity].
     pub fn tick(&mut self) {
         let mut cum = self.accumulators[self.current_cum_buf].take().unwrap();
         let inputs = self.last_accumulator_buf();
-        let neuron_count = self.params.tresholds.len();
-
-        // think of the neurons as being arranged in a circle.
-        // for any given neuron, we observe a slice of this circle with
-        // a size specified by self.connection_count where the current
-        // neuron is at the center of this slice. for each neuron in the slice, we add  
-        // the effect of it on the current neuron if the input is above the treshold.
-
-        // amount of neurons before and after any neuron (on the circle).
-        let extent_back = self.connection_count / 2;
-        let extent_front = if extent_back == 0 {
-            0
-        } else if self.connection_count % 2 != 0 {
-            extent_back
-        } else {
-            extent_back - 1
+
+        // safety: it is assumed parameter and accumulator slices do not change size
+        // after construction of the network. Unless self.connection_count is updated as well.
+        unsafe {
+            apply_firing_effects(
+                &self.params.tresholds,
+                &self.params.effects,
+                self.connection_count,
+                inputs,
+                &mut cum,
+            );
+        }
+
+        self.accumulators[self.current_cum_buf] = Some(cum);
+        if self.last_firing.is_some() {
+            self.apply_plasticity();
+        }
+        self.advance_cum_buf();
+    }
+
+    /// Apply the effect changes accumulated since the last reward, scaled by `reward`,
+    /// then clear them. See [Plasticity::reward_shift].  
+    /// Does nothing when reward modulated [Plasticity] is not enabled.
+    pub fn apply_reward(&mut self, reward: i32) {
+        let traces = match self.eligibility.as_mut() {
+            Some(traces) => traces,
+            None => return,
         };
+        let divisor = 1i64 << self.params.plasticity.reward_shift.min(62);
 
-        for src in 0..extent_back {
-            // safety: it is assumed parameter and accumulator slices do not change size
-            // after construction of the network. Unless self.connection_count is updated as well.
-            unsafe {
-                let input = inputs.get_unchecked(src);
-                let treshold = self.params.tresholds.get_unchecked(src);
-
-                if input >= treshold {
-                    let wrapping_range = neuron_count - extent_back + src..neuron_count;
-                    self.apply_effects(
-                        &mut cum,
-                        src,
-                        0..self.connection_count - wrapping_range.len(),
-                        wrapping_range.len(),
-                    );
-                    self.apply_effects(
-                        &mut cum,
-                        src,
-                        wrapping_range,
-                        0,
-                    );
+        for (i, (effect, trace)) in self.params.effects.iter_mut().zip(traces.iter_mut()).enumerate() {
+            let delta = *trace as i64 * reward as i64 / divisor;
+            effect.0 = (effect.0 as i64 + delta).clamp(i8::MIN as i64, i8::MAX as i64) as i8;
+            if let Some(types) = &self.params.neuron_types {
+                *effect = types[i / self.connection_count].clamp(*effect);
+            }
+            if let Some(mask) = &self.params.connection_mask {
+                if !mask[i] {
+                    *effect = Effect(0);
                 }
             }
+            *trace = 0;
         }
+    }
 
-        for src in extent_back..neuron_count - extent_front {
-            unsafe {
-                let input = inputs.get_unchecked(src);
-                let treshold = self.params.tresholds.get_unchecked(src);
-
-                if input >= treshold {
-                    self.apply_effects(
-                        &mut cum,
-                        src,
-                        src - extent_back..src + extent_front + 1,
-                        0,
-                    );
-                }
+    /// Change effects according to [Plasticity], must be called before advancing the accumulator buffer.
+    fn apply_plasticity(&mut self) {
+        let inputs = self.accumulators[self.last_accumulator_buf_index()].as_ref().unwrap();
+        let last_firing = self.last_firing.as_mut().unwrap();
+        let tresholds = &self.params.tresholds;
+        let effects = &mut self.params.effects;
+        let neuron_types = self.params.neuron_types.as_deref();
+        let connection_mask = self.params.connection_mask.as_deref();
+        let neuron_count = tresholds.len();
+        let connection_count = self.connection_count;
+        let potentiation = self.params.plasticity.potentiation as i32;
+        let depression = self.params.plasticity.depression as i32;
+        let mut eligibility = self.eligibility.as_mut();
+
+        if let Some(traces) = eligibility.as_mut() {
+            let divisor = 1 << self.params.plasticity.trace_decay.min(16);
+            for trace in traces.iter_mut() {
+                *trace -= (*trace as i32 / divisor) as i16;
             }
         }
 
-        for src in neuron_count - extent_front..neuron_count {
-            unsafe {
-                let input = inputs.get_unchecked(src);
-                let treshold = self.params.tresholds.get_unchecked(src);
-
-                if input >= treshold {
-                    let non_wrapping_range = src - extent_back..neuron_count;
-                    self.apply_effects(
-                        &mut cum,
-                        src,
-                        0..self.connection_count - non_wrapping_range.len(),
-                        non_wrapping_range.len(),
-                    );
-                    self.apply_effects(
-                        &mut cum,
-                        src,
-                        non_wrapping_range,
-                        0,
-                    );
+        let firing = |neuron: usize| inputs[neuron] >= tresholds[neuron];
+
+        for src in 0..neuron_count {
+            let src_fired = last_firing[src];
+            let src_firing = firing(src);
+            if !src_fired && !src_firing {
+                continue;
+            }
+
+            for column in 0..connection_count {
+                let dst = connection_target(src, column, neuron_count, connection_count);
+                let mut delta = 0;
+                if src_fired && firing(dst) {
+                    delta += potentiation;
+                }
+                if src_firing && last_firing[dst] {
+                    delta -= depression;
+                }
+
+                if delta == 0 {
+                    continue;
+                }
+                let i = src * connection_count + column;
+                match eligibility.as_mut() {
+                    Some(traces) => {
+                        traces[i] = (traces[i] as i32 + delta).clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+                    }
+                    None => {
+                        effects[i].0 = (effects[i].0 as i32 + delta).clamp(i8::MIN as i32, i8::MAX as i32) as i8;
+                        if let Some(types) = neuron_types {
+                            effects[i] = types[src].clamp(effects[i]);
+                        }
+                        if let Some(mask) = connection_mask {
+                            if !mask[i] {
+                                effects[i] = Effect(0);
+                            }
+                        }
+                    }
                 }
             }
         }
 
-        self.accumulators[self.current_cum_buf] = Some(cum);
-        self.advance_cum_buf();
+        for (neuron, fired) in last_firing.iter_mut().enumerate() {
+            *fired = firing(neuron);
+        }
     }
 
     #[inline]
@@ -320,21 +664,6 @@ impl Network {
         (self.current_cum_buf + ACCUMULATOR_BUF_COUNT - 1) % ACCUMULATOR_BUF_COUNT
     }
 
-    #[inline]
-    unsafe fn apply_effects(
-        &self,
-        cum: &mut [NeuronValue],
-        src: usize,
-        dst_range: Range<usize>,
-        offset: usize,
-    ) {
-        let base = (src * self.connection_count) + offset;
-        for (i, dst) in dst_range.enumerate() {
-            let effect = self.params.effects.get_unchecked(base + i);
-            cum.get_unchecked_mut(dst).0 += effect.0 as i32;
-        }
-    }
-
     #[inline]
     fn advance_cum_buf(&mut self) {
         let i = (self.current_cum_buf + 1) % ACCUMULATOR_BUF_COUNT;
@@ -348,28 +677,227 @@ impl Network {
     }
 }
 
+/// The neuron that the connection in `column` of the effects row of `src` goes to.
+#[inline]
+pub(crate) fn connection_target(src: usize, column: usize, neuron_count: usize, connection_count: usize) -> usize {
+    (src + neuron_count - connection_count / 2 + column) % neuron_count
+}
+
+/// Check the shape of a set of parameters, returning the amount of connections per neuron.
+/// See [Network::with_params] for the errors.
+fn validate(params: ParamsRef) -> Result<usize, Error> {
+    let ParamsRef { tresholds, effects, input_neurons, output_neurons, neuron_types, connection_mask } = params;
+    let neuron_count = tresholds.len();
+    if neuron_count == 0 {
+        return Err(Error::ZeroNeurons);
+    }
+
+    let connection_count = effects.len() / neuron_count;
+    if connection_count == 0 {
+        return Err(Error::ZeroConnections);
+    }
+
+    if connection_count > neuron_count {
+        return Err(Error::TooManyConnections);
+    }
+
+    if !(
+        input_neurons.iter().copied().all(|i| i < neuron_count) &&
+        output_neurons.iter().copied().all(|i| i < neuron_count)
+    ) {
+        return Err(Error::InvalidNeuronIndex);
+    }
+
+    if let Some(types) = neuron_types {
+        if types.len() != neuron_count {
+            return Err(Error::InvalidNeuronTypes);
+        }
+        let rows = effects.chunks_exact(connection_count);
+        if !types.iter().zip(rows).all(|(t, row)| row.iter().all(|&e| t.allows(e))) {
+            return Err(Error::EffectSign);
+        }
+    }
+
+    if let Some(mask) = connection_mask {
+        if mask.len() != effects.len() {
+            return Err(Error::InvalidConnectionMask);
+        }
+        if mask.iter().zip(effects.iter()).any(|(&enabled, e)| !enabled && e.0 != 0) {
+            return Err(Error::DisabledEffect);
+        }
+    }
+
+    Ok(connection_count)
+}
+
+/// Add the effects of every neuron whose input is at or above its treshold to `cum`.
+/// # Safety
+/// `inputs` and `cum` must have an element for every neuron, `effects` must have
+/// `connection_count` elements for every neuron and `connection_count` must be between 1
+/// and the amount of neurons.
+#[inline]
+unsafe fn apply_firing_effects(
+    tresholds: &[NeuronValue],
+    effects: &[Effect],
+    connection_count: usize,
+    inputs: &[NeuronValue],
+    cum: &mut [NeuronValue],
+) {
+    let neuron_count = tresholds.len();
+
+    // think of the neurons as being arranged in a circle.
+    // for any given neuron, we observe a slice of this circle with
+    // a size specified by connection_count where the current
+    // neuron is at the center of this slice. for each neuron in the slice, we add  
+    // the effect of it on the current neuron if the input is above the treshold.
+
+    // amount of neurons before and after any neuron (on the circle).
+    let extent_back = connection_count / 2;
+    let extent_front = if extent_back == 0 {
+        0
+    } else if !connection_count.is_multiple_of(2) {
+        extent_back
+    } else {
+        extent_back - 1
+    };
+
+    for src in 0..extent_back {
+        let input = inputs.get_unchecked(src);
+        let treshold = tresholds.get_unchecked(src);
+
+        if input >= treshold {
+            let wrapping_range = neuron_count - extent_back + src..neuron_count;
+            apply_effects(
+                effects,
+                connection_count,
+                cum,
+                src,
+                0..connection_count - wrapping_range.len(),
+                wrapping_range.len(),
+            );
+            apply_effects(
+                effects,
+                connection_count,
+                cum,
+                src,
+                wrapping_range,
+                0,
+            );
+        }
+    }
+
+    for src in extent_back..neuron_count - extent_front {
+        let input = inputs.get_unchecked(src);
+        let treshold = tresholds.get_unchecked(src);
+
+        if input >= treshold {
+            apply_effects(
+                effects,
+                connection_count,
+                cum,
+                src,
+                src - extent_back..src + extent_front + 1,
+                0,
+            );
+        }
+    }
+
+    for src in neuron_count - extent_front..neuron_count {
+        let input = inputs.get_unchecked(src);
+        let treshold = tresholds.get_unchecked(src);
+
+        if input >= treshold {
+            let non_wrapping_range = src - extent_back..neuron_count;
+            apply_effects(
+                effects,
+                connection_count,
+                cum,
+                src,
+                0..connection_count - non_wrapping_range.len(),
+                non_wrapping_range.len(),
+            );
+            apply_effects(
+                effects,
+                connection_count,
+                cum,
+                src,
+                non_wrapping_range,
+                0,
+            );
+        }
+    }
+}
+
+#[inline]
+unsafe fn apply_effects(
+    effects: &[Effect],
+    connection_count: usize,
+    cum: &mut [NeuronValue],
+    src: usize,
+    dst_range: Range<usize>,
+    offset: usize,
+) {
+    let base = (src * connection_count) + offset;
+    for (i, dst) in dst_range.enumerate() {
+        let effect = effects.get_unchecked(base + i);
+        cum.get_unchecked_mut(dst).0 += effect.0 as i32;
+    }
+}
+
+/// Add `inputs` to the accumulators of the input neurons, see [Network::apply_inputs].
+/// # Safety
+/// Every input neuron must be a valid index into `cum`.
+#[inline]
+unsafe fn add_inputs(cum: &mut [NeuronValue], input_neurons: &[usize], inputs: &[NeuronValue]) {
+    assert_eq!(input_neurons.len(), inputs.len());
+
+    input_neurons
+        .iter()
+        .copied()
+        .zip(inputs.iter().copied())
+        .for_each(|(neuron, input)| {
+            cum.get_unchecked_mut(neuron).0 += input.0;
+        });
+}
+
+/// Copy the accumulators of the output neurons to `outputs`, see [Network::read_outputs].
+/// # Safety
+/// Every output neuron must be a valid index into `cum`.
+#[inline]
+unsafe fn copy_outputs(cum: &[NeuronValue], output_neurons: &[usize], outputs: &mut [NeuronValue]) {
+    assert_eq!(output_neurons.len(), outputs.len());
+
+    output_neurons
+        .iter()
+        .copied()
+        .zip(outputs.iter_mut())
+        .for_each(|(neuron, output)| {
+            *output = *cum.get_unchecked(neuron);
+        });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn input_validation() {
-        match Network::new(0, 1, 0, 0) {
+        match Network::from_seed(0, 1, 0, 0, 0) {
             Err(Error::ZeroNeurons) => (),
             _ => panic!(),
         }
 
-        match Network::new(1, 0, 0, 0) {
+        match Network::from_seed(1, 0, 0, 0, 0) {
             Err(Error::ZeroConnections) => (),
             _ => panic!(),
         }
 
-        match Network::new(1, 2, 0, 0) {
+        match Network::from_seed(1, 2, 0, 0, 0) {
             Err(Error::TooManyConnections) => (),
             _ => panic!(),
         }
 
-        match Network::new(usize::MAX, 2, 0, 0) {
+        match Network::from_seed(usize::MAX, 2, 0, 0, 0) {
             Err(Error::EffectCountOverflow) => (),
             _ => panic!(),
         }
@@ -379,6 +907,9 @@ mod tests {
             effects: vec![Effect(0)].into(),
             input_neurons: vec![].into(),
             output_neurons: vec![].into(),
+            plasticity: Plasticity::default(),
+            neuron_types: None,
+            connection_mask: None,
         };
         match Network::with_params(params) {
             Err(Error::ZeroNeurons) => (),
@@ -390,6 +921,9 @@ mod tests {
             effects: vec![Effect(0)].into(),
             input_neurons: vec![].into(),
             output_neurons: vec![].into(),
+            plasticity: Plasticity::default(),
+            neuron_types: None,
+            connection_mask: None,
         };
         match Network::with_params(params) {
             Err(Error::ZeroConnections) => (),
@@ -401,6 +935,9 @@ mod tests {
             effects: vec![Effect(0); 6].into(),
             input_neurons: vec![].into(),
             output_neurons: vec![].into(),
+            plasticity: Plasticity::default(),
+            neuron_types: None,
+            connection_mask: None,
         };
         match Network::with_params(params) {
             Err(Error::TooManyConnections) => (),
@@ -412,6 +949,9 @@ mod tests {
             effects: vec![Effect(0); 4].into(),
             input_neurons: vec![2usize].into(),
             output_neurons: vec![].into(),
+            plasticity: Plasticity::default(),
+            neuron_types: None,
+            connection_mask: None,
         };
         match Network::with_params(params) {
             Err(Error::InvalidNeuronIndex) => (),
@@ -423,6 +963,9 @@ mod tests {
             effects: vec![Effect(0); 4].into(),
             input_neurons: vec![].into(),
             output_neurons: vec![2usize].into(),
+            plasticity: Plasticity::default(),
+            neuron_types: None,
+            connection_mask: None,
         };
         match Network::with_params(params) {
             Err(Error::InvalidNeuronIndex) => (),
@@ -460,6 +1003,9 @@ mod tests {
             effects,
             input_neurons,
             output_neurons,
+            plasticity: Plasticity::default(),
+            neuron_types: None,
+            connection_mask: None,
         }).unwrap();
 
         // first evaluate 2 ticks for expected output
@@ -517,4 +1063,148 @@ mod tests {
             ],
         );
     }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn params_format() {
+        let net = Network::new(16, 3, 4, 2).unwrap();
+        let params = NetworkParams {
+            plasticity: Plasticity {
+                enabled: true,
+                potentiation: 3,
+                ..Plasticity::default()
+            },
+            ..net.extract_params()
+        };
+
+        let mut buf = vec![];
+        params.write_to(&mut buf).unwrap();
+        assert_eq!(NetworkParams::read_from(&mut buf.as_slice()).unwrap(), params);
+
+        match NetworkParams::read_from(&mut &buf[..buf.len() - 1]) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+            _ => panic!(),
+        }
+
+        buf[0] = b'X';
+        match NetworkParams::read_from(&mut buf.as_slice()) {
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn plasticity() {
+        let mut net = Network::with_params(NetworkParams {
+            tresholds: vec![NeuronValue(0), NeuronValue(1), NeuronValue(1000)].into(),
+            effects: vec![
+                Effect(0), Effect(0), Effect(5),
+                Effect(0), Effect(0), Effect(0),
+                Effect(0), Effect(0), Effect(0),
+            ].into(),
+            input_neurons: vec![].into(),
+            output_neurons: vec![].into(),
+            plasticity: Plasticity {
+                enabled: true,
+                potentiation: 2,
+                depression: 1,
+                ..Plasticity::default()
+            },
+            neuron_types: None,
+            connection_mask: None,
+        }).unwrap();
+
+        // only neuron 0 fires, nothing fired before
+        net.tick();
+        assert_eq!(net.params().effects[..3], [Effect(0), Effect(0), Effect(5)]);
+
+        // neuron 1 fires after neuron 0 and neuron 0 fires after itself
+        net.tick();
+        assert_eq!(
+            net.params().effects.as_ref(),
+            &[
+                Effect(0), Effect(1), Effect(7),
+                Effect(-1), Effect(0), Effect(0),
+                Effect(0), Effect(0), Effect(0),
+            ],
+        );
+    }
+
+    #[test]
+    fn reward_modulated_plasticity() {
+        let effects: Box<[Effect]> = vec![
+            Effect(0), Effect(0), Effect(5),
+            Effect(0), Effect(0), Effect(0),
+            Effect(0), Effect(0), Effect(0),
+        ].into();
+        let mut net = Network::with_params(NetworkParams {
+            tresholds: vec![NeuronValue(0), NeuronValue(1), NeuronValue(1000)].into(),
+            effects: effects.clone(),
+            input_neurons: vec![].into(),
+            output_neurons: vec![].into(),
+            plasticity: Plasticity {
+                enabled: true,
+                potentiation: 2,
+                depression: 1,
+                reward_modulated: true,
+                trace_decay: 16,
+                reward_shift: 1,
+            },
+            neuron_types: None,
+            connection_mask: None,
+        }).unwrap();
+
+        net.tick();
+        net.tick();
+        assert_eq!(net.params().effects, effects);
+
+        net.apply_reward(4);
+        assert_eq!(
+            net.params().effects.as_ref(),
+            &[
+                Effect(0), Effect(2), Effect(9),
+                Effect(-2), Effect(0), Effect(0),
+                Effect(0), Effect(0), Effect(0),
+            ],
+        );
+
+        // traces are cleared by a reward
+        net.apply_reward(4);
+        assert_eq!(net.params().effects[..4], [Effect(0), Effect(2), Effect(9), Effect(-2)]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn neuron_types() {
+        let init = InitParams { excitatory: Some((1, 2)), ..InitParams::default() };
+        let mut params = NetworkParams::random(32, 5, 2, 2, &init, &mut ChaCha8Rng::seed_from_u64(1234)).unwrap();
+        let types = params.neuron_types.clone().unwrap();
+        assert!(types.contains(&NeuronType::Excitatory) && types.contains(&NeuronType::Inhibitory));
+        assert!(params.effects.iter().any(|e| e.0 > 0) && params.effects.iter().any(|e| e.0 < 0));
+
+        let mut buf = vec![];
+        params.write_to(&mut buf).unwrap();
+        assert_eq!(NetworkParams::read_from(&mut buf.as_slice()).unwrap(), params);
+
+        let mut net = Network::with_params(NetworkParams {
+            plasticity: Plasticity {
+                enabled: true,
+                potentiation: 100,
+                depression: 100,
+                ..Plasticity::default()
+            },
+            ..params.clone()
+        }).unwrap();
+        for _ in 0..20 {
+            net.apply_inputs(&[NeuronValue(1000); 2]);
+            net.tick();
+        }
+        assert!(Network::with_params(net.extract_params()).is_ok());
+
+        let excitatory = types.iter().position(|&t| t == NeuronType::Excitatory).unwrap();
+        params.effects[excitatory * 5] = Effect(-1);
+        assert!(matches!(Network::with_params(params.clone()), Err(Error::EffectSign)));
+        params.neuron_types = Some(types[1..].into());
+        assert!(matches!(Network::with_params(params), Err(Error::InvalidNeuronTypes)));
+    }
 }
@@ -0,0 +1,188 @@
+//! A [Network] that runs on parameters and buffers stored elsewhere, without allocating.
+
+use super::*;
+
+/// Borrowed network parameters, see [NetworkParams].
+/// Parameters stored in flash can be borrowed as well, e.g with
+/// [Effect::from_bytes] on the output of `include_bytes!`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParamsRef<'a> {
+    pub tresholds: &'a [NeuronValue],
+    pub effects: &'a [Effect],
+    pub input_neurons: &'a [usize],
+    pub output_neurons: &'a [usize],
+    pub neuron_types: Option<&'a [NeuronType]>,
+    pub connection_mask: Option<&'a [bool]>,
+}
+
+impl<'a> From<&'a NetworkParams> for ParamsRef<'a> {
+    fn from(params: &'a NetworkParams) -> Self {
+        Self {
+            tresholds: &params.tresholds,
+            effects: &params.effects,
+            input_neurons: &params.input_neurons,
+            output_neurons: &params.output_neurons,
+            neuron_types: params.neuron_types.as_deref(),
+            connection_mask: params.connection_mask.as_deref(),
+        }
+    }
+}
+
+/// A [Network] over borrowed parameters and a caller provided accumulator buffer.
+/// Ticks behave exactly like [Network::tick], except that [Plasticity] is not supported
+/// since the effects can not be changed.
+///
+/// ```
+/// use siarne::network::{Effect, NetworkRef, NeuronValue, ParamsRef};
+///
+/// static TRESHOLDS: [NeuronValue; 4] = [NeuronValue(0); 4];
+/// static EFFECTS: &[u8] = &[1; 4 * 3];
+///
+/// let mut accumulators = [NeuronValue(0); 2 * 4];
+/// let params = ParamsRef {
+///     tresholds: &TRESHOLDS,
+///     effects: Effect::from_bytes(EFFECTS),
+///     input_neurons: &[0],
+///     output_neurons: &[1],
+///     neuron_types: None,
+///     connection_mask: None,
+/// };
+/// let mut net = NetworkRef::new(params, &mut accumulators).unwrap();
+///
+/// net.tick();
+/// let mut outputs = [NeuronValue(0)];
+/// net.read_outputs(&mut outputs);
+/// assert_eq!(outputs, [NeuronValue(3)]);
+/// ```
+pub struct NetworkRef<'a> {
+    params: ParamsRef<'a>,
+    connection_count: usize,
+    accumulators: [&'a mut [NeuronValue]; ACCUMULATOR_BUF_COUNT],
+    current_cum_buf: usize,
+}
+
+impl<'a> NetworkRef<'a> {
+    /// Create a [NetworkRef], using `accumulators` as the buffers that a tick reads from
+    /// and writes to. The buffer is zeroed.
+    /// # Errors
+    /// See [Network::with_params].
+    /// # Panics
+    /// When `accumulators.len()` is not equal to 2 times the neuron count.
+    pub fn new(params: ParamsRef<'a>, accumulators: &'a mut [NeuronValue]) -> Result<Self, Error> {
+        let connection_count = validate(params)?;
+        let neuron_count = params.tresholds.len();
+        assert_eq!(accumulators.len(), ACCUMULATOR_BUF_COUNT * neuron_count);
+
+        accumulators.fill(NeuronValue(0));
+        let (a, b) = accumulators.split_at_mut(neuron_count);
+
+        Ok(Self {
+            params,
+            connection_count,
+            accumulators: [a, b],
+            current_cum_buf: 0,
+        })
+    }
+
+    /// Returns this [NetworkRef]'s parameters.
+    pub fn params(&self) -> ParamsRef<'a> {
+        self.params
+    }
+
+    /// See [Network::apply_inputs].
+    /// # Panics
+    /// When `inputs.len()` is not equal to the input neuron count.
+    pub fn apply_inputs(&mut self, inputs: &[NeuronValue]) {
+        let index = self.last_accumulator_buf_index();
+        // safety: the input neurons are validated on construction
+        unsafe {
+            add_inputs(self.accumulators[index], self.params.input_neurons, inputs);
+        }
+    }
+
+    /// See [Network::accumulators].
+    pub fn accumulators(&self) -> &[NeuronValue] {
+        self.accumulators[self.last_accumulator_buf_index()]
+    }
+
+    /// See [Network::read_outputs].
+    /// # Panics
+    /// When `outputs.len()` is not equal to the output neuron count.
+    pub fn read_outputs(&self, outputs: &mut [NeuronValue]) {
+        // safety: the output neurons are validated on construction
+        unsafe {
+            copy_outputs(self.accumulators(), self.params.output_neurons, outputs);
+        }
+    }
+
+    /// See [Network::tick].
+    pub fn tick(&mut self) {
+        let last = self.last_accumulator_buf_index();
+        let [a, b] = &mut self.accumulators;
+        let (inputs, cum) = if last == 0 { (&**a, &mut **b) } else { (&**b, &mut **a) };
+
+        // safety: the parameters are validated on construction and the accumulator
+        // buffers have an element for every neuron
+        unsafe {
+            apply_firing_effects(
+                self.params.tresholds,
+                self.params.effects,
+                self.connection_count,
+                inputs,
+                cum,
+            );
+        }
+
+        self.current_cum_buf = (self.current_cum_buf + 1) % ACCUMULATOR_BUF_COUNT;
+        self.accumulators[self.current_cum_buf].fill(NeuronValue(0));
+    }
+
+    #[inline]
+    fn last_accumulator_buf_index(&self) -> usize {
+        (self.current_cum_buf + ACCUMULATOR_BUF_COUNT - 1) % ACCUMULATOR_BUF_COUNT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_as_owned() {
+        let mut params = Network::from_seed(64, 9, 4, 4, 1234).unwrap().extract_params();
+        params.tresholds.iter_mut().for_each(|t| t.0 %= 64);
+        let mut net = Network::with_params(params.clone()).unwrap();
+
+        let mut accumulators = vec![NeuronValue(1234); 2 * 64];
+        let mut borrowed = NetworkRef::new((&params).into(), &mut accumulators).unwrap();
+
+        let inputs = [NeuronValue(100), NeuronValue(-5), NeuronValue(7), NeuronValue(0)];
+        let mut outputs = [NeuronValue(0); 4];
+        let mut borrowed_outputs = [NeuronValue(0); 4];
+        for _ in 0..100 {
+            net.apply_inputs(&inputs);
+            borrowed.apply_inputs(&inputs);
+            net.tick();
+            borrowed.tick();
+
+            assert_eq!(net.accumulators(), borrowed.accumulators());
+            net.read_outputs(&mut outputs);
+            borrowed.read_outputs(&mut borrowed_outputs);
+            assert_eq!(outputs, borrowed_outputs);
+        }
+    }
+
+    #[test]
+    fn invalid_params() {
+        let mut accumulators = [NeuronValue(0); 4];
+        let params = ParamsRef {
+            tresholds: &[NeuronValue(0); 2],
+            effects: &[Effect(0); 4],
+            input_neurons: &[2],
+            output_neurons: &[],
+            neuron_types: None,
+            connection_mask: None,
+        };
+        assert!(matches!(NetworkRef::new(params, &mut accumulators), Err(Error::InvalidNeuronIndex)));
+    }
+}
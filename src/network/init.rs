@@ -0,0 +1,253 @@
+//! Random initialization of [NetworkParams].
+
+use super::*;
+
+use alloc::vec;
+use rand::{distributions::Bernoulli, Rng};
+
+/// The distribution of the initial tresholds, see [InitParams].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TresholdInit {
+    /// Uniform over every [i32].
+    Uniform,
+    /// Uniform over `min..=max`.
+    Range { min: i32, max: i32 },
+    /// Uniform over the range the input of a neuron can reach through its connections,
+    /// `-127 * connection_count..=127 * connection_count`.
+    Reachable,
+}
+
+/// The distribution of the initial effects, see [InitParams].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EffectInit {
+    /// Uniform over every [i8].
+    Uniform,
+    /// Uniform over `min..=max`.
+    Range { min: i8, max: i8 },
+    /// Every effect is 0.
+    Zero,
+    /// Each effect is non-zero with probability `density.0 / density.1`,
+    /// uniform over every non-zero [i8] when it is.
+    Sparse { density: (u32, u32) },
+    /// Each effect is positive with probability `excitatory.0 / excitatory.1` and negative otherwise,
+    /// with a magnitude uniform over `1..=127`.
+    Balanced { excitatory: (u32, u32) },
+}
+
+/// How [NetworkParams::random] initializes parameters.
+/// The default matches the parameters of [Network::new].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InitParams {
+    pub tresholds: TresholdInit,
+    pub effects: EffectInit,
+    /// When set, each neuron is [NeuronType::Excitatory] with probability `excitatory.0 / excitatory.1`
+    /// and [NeuronType::Inhibitory] otherwise, and effects of the wrong sign are negated.
+    pub excitatory: Option<(u32, u32)>,
+}
+
+impl Default for InitParams {
+    fn default() -> Self {
+        Self {
+            tresholds: TresholdInit::Uniform,
+            effects: EffectInit::Uniform,
+            excitatory: None,
+        }
+    }
+}
+
+impl NetworkParams {
+    /// Generate parameters with values drawn from the distributions in `init` using `rng`.
+    /// Input and output neurons are uniform over all neurons.
+    /// The result only depends on the arguments and the state of `rng`.
+    /// # Errors
+    /// See [Network::from_seed].
+    /// # Panics
+    /// When a range in `init` is empty or a density is not a valid probability.
+    pub fn random<R: Rng + ?Sized>(
+        neuron_count: usize,
+        connection_count: usize,
+        input_count: usize,
+        output_count: usize,
+        init: &InitParams,
+        rng: &mut R,
+    ) -> Result<Self, Error> {
+        if neuron_count == 0 {
+            return Err(Error::ZeroNeurons);
+        }
+        if connection_count == 0 {
+            return Err(Error::ZeroConnections);
+        }
+        if connection_count > neuron_count {
+            return Err(Error::TooManyConnections);
+        }
+
+        let effect_count = neuron_count
+            .checked_mul(connection_count)
+            .ok_or(Error::EffectCountOverflow)?;
+
+        let reachable = (i8::MAX as i64 * connection_count as i64).min(i32::MAX as i64) as i32;
+        let tresholds = iter::repeat_with(|| match init.tresholds {
+            TresholdInit::Uniform => NeuronValue(rng.gen()),
+            TresholdInit::Range { min, max } => NeuronValue(rng.gen_range(min..=max)),
+            TresholdInit::Reachable => NeuronValue(rng.gen_range(-reachable..=reachable)),
+        })
+        .take(neuron_count)
+        .collect();
+
+        let neuron_types: Option<Box<[NeuronType]>> = init.excitatory.map(|excitatory| {
+            let excitatory = Bernoulli::from_ratio(excitatory.0, excitatory.1).unwrap();
+            iter::repeat_with(|| match rng.sample(excitatory) {
+                true => NeuronType::Excitatory,
+                false => NeuronType::Inhibitory,
+            })
+            .take(neuron_count)
+            .collect()
+        });
+
+        let ratio = match init.effects {
+            EffectInit::Sparse { density: ratio } | EffectInit::Balanced { excitatory: ratio } => {
+                Some(Bernoulli::from_ratio(ratio.0, ratio.1).unwrap())
+            }
+            _ => None,
+        };
+        let mut effects: Box<[Effect]> = iter::repeat_with(|| match init.effects {
+            EffectInit::Uniform => Effect(rng.gen()),
+            EffectInit::Range { min, max } => Effect(rng.gen_range(min..=max)),
+            EffectInit::Zero => Effect(0),
+            EffectInit::Sparse { .. } => {
+                if rng.sample(ratio.unwrap()) {
+                    // i8 has an even amount of values, so shift the positive ones up by 1
+                    let effect = rng.gen_range(i8::MIN..i8::MAX);
+                    Effect(if effect >= 0 { effect + 1 } else { effect })
+                } else {
+                    Effect(0)
+                }
+            }
+            EffectInit::Balanced { .. } => {
+                let magnitude = rng.gen_range(1..=i8::MAX);
+                Effect(if rng.sample(ratio.unwrap()) { magnitude } else { -magnitude })
+            }
+        })
+        .take(effect_count)
+        .collect();
+
+        if let Some(types) = &neuron_types {
+            for (row, &neuron_type) in effects.chunks_mut(connection_count).zip(types.iter()) {
+                for effect in row.iter_mut().filter(|e| !neuron_type.allows(**e)) {
+                    effect.0 = effect.0.saturating_neg();
+                }
+            }
+        }
+
+        let neuron_dist = rand::distributions::Uniform::from(0..neuron_count);
+
+        let input_neurons = iter::repeat_with(|| rng.sample(neuron_dist))
+            .take(input_count)
+            .collect();
+
+        let output_neurons = iter::repeat_with(|| rng.sample(neuron_dist))
+            .take(output_count)
+            .collect();
+
+        Ok(Self {
+            tresholds,
+            effects,
+            input_neurons,
+            output_neurons,
+            plasticity: Plasticity::default(),
+            neuron_types,
+            connection_mask: None,
+        })
+    }
+
+    /// The fraction of neurons that can fire at all, i.e whose treshold is at most the sum of
+    /// the positive effects of the connections to it. Input neurons can always fire,
+    /// since the inputs applied to them are not bounded.
+    /// # Panics
+    /// When the amount of effects is not a multiple of the amount of neurons.
+    pub fn fireable_fraction(&self) -> f64 {
+        let neuron_count = self.tresholds.len();
+        if neuron_count == 0 {
+            return 0.0;
+        }
+        let connection_count = self.effects.len() / neuron_count;
+        assert_eq!(neuron_count * connection_count, self.effects.len());
+
+        let mut max_inputs = vec![0i64; neuron_count];
+        for (i, effect) in self.effects.iter().enumerate() {
+            let dst = connection_target(i / connection_count, i % connection_count, neuron_count, connection_count);
+            max_inputs[dst] += effect.0.max(0) as i64;
+        }
+        for &neuron in self.input_neurons.iter() {
+            max_inputs[neuron] = i64::MAX;
+        }
+
+        let fireable = self.tresholds
+            .iter()
+            .zip(max_inputs.iter())
+            .filter(|(treshold, &max_input)| treshold.0 as i64 <= max_input)
+            .count();
+        fireable as f64 / neuron_count as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn init() {
+        let net = Network::from_seed(64, 8, 4, 4, 1234).unwrap();
+        assert_eq!(net.params(), Network::from_seed(64, 8, 4, 4, 1234).unwrap().params());
+        assert_ne!(net.params(), Network::from_seed(64, 8, 4, 4, 1235).unwrap().params());
+
+        let mut rng = ChaCha8Rng::seed_from_u64(1234);
+        let init = InitParams {
+            tresholds: TresholdInit::Range { min: -10, max: 10 },
+            effects: EffectInit::Zero,
+            ..InitParams::default()
+        };
+        let params = NetworkParams::random(64, 8, 0, 0, &init, &mut rng).unwrap();
+        assert!(params.tresholds.iter().all(|t| (-10..=10).contains(&t.0)));
+        assert!(params.effects.iter().all(|e| e.0 == 0));
+
+        let init = InitParams {
+            tresholds: TresholdInit::Uniform,
+            effects: EffectInit::Sparse { density: (1, 4) },
+            ..InitParams::default()
+        };
+        let params = NetworkParams::random(256, 16, 0, 0, &init, &mut rng).unwrap();
+        let non_zero = params.effects.iter().filter(|e| e.0 != 0).count();
+        assert!(non_zero > 900 && non_zero < 1150);
+
+        assert!(matches!(
+            NetworkParams::random(0, 0, 0, 0, &init, &mut rng),
+            Err(Error::ZeroNeurons),
+        ));
+    }
+
+    #[test]
+    fn reachable() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1234);
+        let uniform = NetworkParams::random(256, 16, 0, 0, &InitParams::default(), &mut rng).unwrap();
+        assert!(uniform.fireable_fraction() < 0.6);
+
+        let init = InitParams {
+            tresholds: TresholdInit::Reachable,
+            effects: EffectInit::Balanced { excitatory: (1, 2) },
+            ..InitParams::default()
+        };
+        let params = NetworkParams::random(256, 16, 0, 0, &init, &mut rng).unwrap();
+        assert!(params.tresholds.iter().all(|t| t.0.abs() <= 127 * 16));
+        assert!(params.effects.iter().all(|e| e.0 != 0 && e.0 != i8::MIN));
+        let excitatory = params.effects.iter().filter(|e| e.0 > 0).count();
+        assert!(excitatory > 1900 && excitatory < 2200);
+        assert!(params.fireable_fraction() > 0.6);
+
+        let mut zero = params.clone();
+        zero.effects.iter_mut().for_each(|e| e.0 = 0);
+        zero.tresholds.iter_mut().for_each(|t| t.0 = 1);
+        zero.input_neurons = vec![0, 1].into();
+        assert_eq!(zero.fireable_fraction(), 2.0 / 256.0);
+    }
+}
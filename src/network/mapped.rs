@@ -0,0 +1,91 @@
+//! Running networks directly on the effects of a memory mapped params file.
+
+use super::*;
+
+use memmap2::Mmap;
+
+/// A params file written by [NetworkParams::write_to] that is memory mapped instead of read,
+/// so the effects are only loaded when used and shared by every process mapping the same file.
+/// Everything but the effects is read into memory on open.
+pub struct MappedParams {
+    map: Mmap,
+    /// The parameters without effects.
+    params: NetworkParams,
+    effects: Range<usize>,
+}
+
+impl MappedParams {
+    /// Map a params file and validate it like [Network::with_params].
+    /// # Safety
+    /// The file must not be modified while it is mapped, see [Mmap::map].
+    /// # Errors
+    /// Any error opening or mapping the file, [io::ErrorKind::InvalidData] when the data is
+    /// not in the expected format, the parameters are not valid or [Plasticity] is enabled,
+    /// since the mapped effects can not change.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let map = Mmap::map(&File::open(path)?)?;
+
+        let mut r = &map[..];
+        let (params, effect_count) = NetworkParams::read_without_effects(&mut r)?;
+        let start = map.len() - r.len();
+        if r.len() < effect_count {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let effects = start..start + effect_count;
+
+        let mapped = ParamsRef {
+            effects: Effect::from_bytes(&map[effects.clone()]),
+            ..ParamsRef::from(&params)
+        };
+        validate(mapped).map_err(|e| invalid_data(&format!("invalid params: {:?}", e)))?;
+        if params.plasticity.enabled {
+            return Err(invalid_data("plasticity is not supported for mapped params"));
+        }
+
+        Ok(Self { map, params, effects })
+    }
+
+    /// The mapped parameters.
+    pub fn params(&self) -> ParamsRef<'_> {
+        ParamsRef {
+            effects: Effect::from_bytes(&self.map[self.effects.clone()]),
+            ..ParamsRef::from(&self.params)
+        }
+    }
+
+    /// Create a network over the mapped parameters, see [NetworkRef::new].
+    /// # Panics
+    /// When `accumulators.len()` is not equal to 2 times the neuron count.
+    pub fn network<'a>(&'a self, accumulators: &'a mut [NeuronValue]) -> NetworkRef<'a> {
+        // the parameters are validated on open
+        NetworkRef::new(self.params(), accumulators).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapped() {
+        let path = std::env::temp_dir().join(format!("siarne-mapped-{}", std::process::id()));
+        let params = Network::new(32, 5, 2, 3).unwrap().extract_params();
+        params.save(&path).unwrap();
+
+        let mapped = unsafe { MappedParams::open(&path) }.unwrap();
+        assert_eq!(mapped.params(), ParamsRef::from(&params));
+
+        let mut accumulators = vec![NeuronValue(0); 2 * 32];
+        let mut net = mapped.network(&mut accumulators);
+        net.tick();
+        drop(mapped);
+
+        let mut truncated = vec![];
+        params.write_to(&mut truncated).unwrap();
+        truncated.pop();
+        std::fs::write(&path, truncated).unwrap();
+        assert!(unsafe { MappedParams::open(&path) }.is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,112 @@
+//! Disabling connections, see [NetworkParams::connection_mask].
+
+use super::*;
+
+/// The amount of disabled connections, see [NetworkParams::sparsity].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sparsity {
+    pub disabled: usize,
+    pub total: usize,
+}
+
+impl Sparsity {
+    /// The fraction of connections that is disabled, 0 when there are no connections.
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.disabled as f64 / self.total as f64
+        }
+    }
+}
+
+impl NetworkParams {
+    /// Enable or disable the connection at `index` into the effects, adding a connection mask
+    /// with every connection enabled when there is none. The effect of a disabled connection is set to 0.
+    /// # Panics
+    /// When `index` is out of bounds.
+    pub fn set_connection_enabled(&mut self, index: usize, enabled: bool) {
+        let effect_count = self.effects.len();
+        let mask = self.connection_mask.get_or_insert_with(|| vec![true; effect_count].into());
+
+        mask[index] = enabled;
+        if !enabled {
+            self.effects[index] = Effect(0);
+        }
+    }
+
+    /// Disable every connection with an effect magnitude below `min_magnitude`,
+    /// returning the resulting sparsity.
+    pub fn prune(&mut self, min_magnitude: u8) -> Sparsity {
+        for i in 0..self.effects.len() {
+            if self.effects[i].0.unsigned_abs() < min_magnitude {
+                self.set_connection_enabled(i, false);
+            }
+        }
+
+        self.sparsity()
+    }
+
+    /// The amount of disabled connections, which is 0 without a connection mask.
+    pub fn sparsity(&self) -> Sparsity {
+        let disabled = self.connection_mask
+            .as_ref()
+            .map_or(0, |mask| mask.iter().filter(|&&enabled| !enabled).count());
+
+        Sparsity {
+            disabled,
+            total: self.effects.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prune() {
+        let mut params = NetworkParams::random(64, 8, 2, 2, &InitParams::default(), &mut ChaCha8Rng::seed_from_u64(1234)).unwrap();
+        assert_eq!(params.sparsity(), Sparsity { disabled: 0, total: 512 });
+
+        let small = params.effects.iter().filter(|e| e.0.unsigned_abs() < 64).count();
+        let sparsity = params.prune(64);
+        assert_eq!(sparsity, Sparsity { disabled: small, total: 512 });
+        assert!((sparsity.fraction() - 0.5).abs() < 0.1);
+
+        let mask = params.connection_mask.clone().unwrap();
+        assert!(params.effects.iter().zip(mask.iter()).all(|(e, &enabled)| enabled == (e.0 != 0)));
+
+        // disabled connections stay at 0 with plasticity
+        let mut net = Network::with_params(NetworkParams {
+            plasticity: Plasticity {
+                enabled: true,
+                potentiation: 100,
+                ..Plasticity::default()
+            },
+            ..params.clone()
+        }).unwrap();
+        for _ in 0..20 {
+            net.apply_inputs(&[NeuronValue(1000); 2]);
+            net.tick();
+        }
+        assert!(Network::with_params(net.extract_params()).is_ok());
+
+        let disabled = mask.iter().position(|&enabled| !enabled).unwrap();
+        params.effects[disabled] = Effect(1);
+        assert!(matches!(Network::with_params(params.clone()), Err(Error::DisabledEffect)));
+        params.connection_mask = Some(mask[1..].into());
+        assert!(matches!(Network::with_params(params), Err(Error::InvalidConnectionMask)));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn mask_format() {
+        let mut params = NetworkParams::random(64, 8, 2, 2, &InitParams::default(), &mut ChaCha8Rng::seed_from_u64(1234)).unwrap();
+        params.prune(64);
+
+        let mut buf = vec![];
+        params.write_to(&mut buf).unwrap();
+        assert_eq!(NetworkParams::read_from(&mut buf.as_slice()).unwrap(), params);
+    }
+}
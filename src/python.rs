@@ -0,0 +1,210 @@
+//! Python bindings, built as the `siarne` extension module.
+//! Tresholds and effects are exposed as read-only NumPy arrays that share memory with the parameters.
+
+use crate::{
+    network::{self, Effect, NetworkParams, NeuronValue, Plasticity},
+    train::evolve::{self, NoisePassParams},
+    Network,
+};
+
+use numpy::{
+    ndarray::{Array2, ArrayView1},
+    Element, IntoPyArray, PyArray1, PyArray2, PyArrayMethods, PyReadonlyArray1,
+};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use std::slice;
+
+fn to_py_err(e: network::Error) -> PyErr {
+    PyValueError::new_err(format!("invalid network: {:?}", e))
+}
+
+fn neuron_values(values: &[NeuronValue]) -> &[i32] {
+    // safety: NeuronValue is a transparent wrapper of i32
+    unsafe { slice::from_raw_parts(values.as_ptr() as *const i32, values.len()) }
+}
+
+fn effect_values(effects: &[Effect]) -> &[i8] {
+    // safety: Effect is a transparent wrapper of i8
+    unsafe { slice::from_raw_parts(effects.as_ptr() as *const i8, effects.len()) }
+}
+
+/// Clear the writeable flag of an array that borrows the parameters, since they are only
+/// borrowed immutably. Python can not set the flag again because the array does not own its data.
+fn read_only<T: Element>(array: Bound<'_, PyArray1<T>>) -> Bound<'_, PyArray1<T>> {
+    array.readwrite().make_nonwriteable();
+    array
+}
+
+/// The parameters of a network, see [NetworkParams].
+#[pyclass(name = "NetworkParams", module = "siarne")]
+pub struct PyNetworkParams {
+    // the tresholds and effects are never reallocated, since arrays borrow them
+    params: NetworkParams,
+}
+
+#[pymethods]
+impl PyNetworkParams {
+    #[new]
+    fn new(
+        tresholds: PyReadonlyArray1<i32>,
+        effects: PyReadonlyArray1<i8>,
+        input_neurons: Vec<usize>,
+        output_neurons: Vec<usize>,
+    ) -> Self {
+        let params = NetworkParams {
+            tresholds: tresholds.as_array().iter().map(|&t| NeuronValue(t)).collect(),
+            effects: effects.as_array().iter().map(|&e| Effect(e)).collect(),
+            input_neurons: input_neurons.into(),
+            output_neurons: output_neurons.into(),
+            plasticity: Plasticity::default(),
+            neuron_types: None,
+            connection_mask: None,
+        };
+        Self { params }
+    }
+
+    /// A read-only int32 array sharing memory with the tresholds.
+    #[getter]
+    fn tresholds<'py>(this: Bound<'py, Self>) -> Bound<'py, PyArray1<i32>> {
+        let params = &this.borrow().params;
+        let view = ArrayView1::from(neuron_values(&params.tresholds));
+        // safety: the tresholds live as long as this object and are never reallocated
+        read_only(unsafe { PyArray1::borrow_from_array(&view, this.clone().into_any()) })
+    }
+
+    /// A read-only int8 array sharing memory with the effects,
+    /// with `connection_count` consecutive effects per neuron.
+    #[getter]
+    fn effects<'py>(this: Bound<'py, Self>) -> Bound<'py, PyArray1<i8>> {
+        let params = &this.borrow().params;
+        let view = ArrayView1::from(effect_values(&params.effects));
+        // safety: the effects live as long as this object and are never reallocated
+        read_only(unsafe { PyArray1::borrow_from_array(&view, this.clone().into_any()) })
+    }
+
+    #[getter]
+    fn input_neurons(&self) -> Vec<usize> {
+        self.params.input_neurons.to_vec()
+    }
+
+    #[getter]
+    fn output_neurons(&self) -> Vec<usize> {
+        self.params.output_neurons.to_vec()
+    }
+
+    /// Apply a noise pass, see [evolve::apply_parameter_noise].
+    fn apply_noise(&mut self, seed: u64, power: u8) {
+        evolve::apply_parameter_noise(&mut self.params, seed, power);
+    }
+
+    fn save(&self, path: &str) -> PyResult<()> {
+        Ok(self.params.save(path)?)
+    }
+
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<Self> {
+        Ok(Self { params: NetworkParams::load(path)? })
+    }
+}
+
+/// A network, see [Network].
+#[pyclass(name = "Network", module = "siarne")]
+pub struct PyNetwork {
+    net: Network,
+}
+
+#[pymethods]
+impl PyNetwork {
+    /// Create a network with a copy of the parameters.
+    #[new]
+    fn new(params: PyRef<PyNetworkParams>) -> PyResult<Self> {
+        let net = Network::with_params(params.params.clone()).map_err(to_py_err)?;
+        Ok(Self { net })
+    }
+
+    /// A copy of the parameters.
+    #[getter]
+    fn params(&self) -> PyNetworkParams {
+        PyNetworkParams { params: self.net.params().clone() }
+    }
+
+    /// A copy of the inputs of all neurons for the next tick.
+    #[getter]
+    fn accumulators<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<i32>> {
+        PyArray1::from_slice(py, neuron_values(self.net.accumulators()))
+    }
+
+    fn apply_inputs(&mut self, inputs: PyReadonlyArray1<i32>) -> PyResult<()> {
+        let inputs: Vec<_> = inputs.as_array().iter().map(|&i| NeuronValue(i)).collect();
+        if inputs.len() != self.net.params().input_neurons.len() {
+            return Err(PyValueError::new_err("wrong amount of inputs"));
+        }
+
+        self.net.apply_inputs(&inputs);
+        Ok(())
+    }
+
+    fn read_outputs<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<i32>> {
+        let mut outputs = vec![NeuronValue(0); self.net.params().output_neurons.len()];
+        self.net.read_outputs(&mut outputs);
+        outputs.into_iter().map(|o| o.0).collect::<Vec<_>>().into_pyarray(py)
+    }
+
+    fn tick(&mut self) {
+        self.net.tick();
+    }
+
+    /// Execute `ticks` ticks, returning an array with the outputs after every tick.
+    fn run<'py>(&mut self, py: Python<'py>, ticks: usize) -> Bound<'py, PyArray2<i32>> {
+        let output_count = self.net.params().output_neurons.len();
+        let mut outputs = vec![NeuronValue(0); ticks * output_count];
+
+        py.detach(|| {
+            for row in outputs.chunks_mut(output_count.max(1)).take(ticks) {
+                self.net.tick();
+                self.net.read_outputs(&mut row[..output_count]);
+            }
+        });
+
+        let outputs = outputs.into_iter().map(|o| o.0).collect();
+        Array2::from_shape_vec((ticks, output_count), outputs)
+            .unwrap()
+            .into_pyarray(py)
+    }
+}
+
+/// Build a network from noise, see [evolve::build_network_from_noise].
+/// `passes` is a list of `(seed, power)` tuples.
+#[pyfunction]
+fn build_network_from_noise(
+    neuron_count: usize,
+    connection_count: usize,
+    input_count: usize,
+    output_count: usize,
+    seed: u64,
+    passes: Vec<(u64, u8)>,
+) -> PyResult<PyNetwork> {
+    let passes = passes
+        .into_iter()
+        .map(|(seed, power)| NoisePassParams { seed, power });
+
+    let net = evolve::build_network_from_noise(
+        neuron_count,
+        connection_count,
+        input_count,
+        output_count,
+        seed,
+        passes,
+    )
+    .map_err(to_py_err)?;
+    Ok(PyNetwork { net })
+}
+
+#[pymodule]
+fn siarne(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyNetworkParams>()?;
+    m.add_class::<PyNetwork>()?;
+    m.add_function(wrap_pyfunction!(build_network_from_noise, m)?)?;
+    Ok(())
+}
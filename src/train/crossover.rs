@@ -0,0 +1,188 @@
+//! Combining the parameters of two [Network](crate::Network)s into new parameters.
+
+use crate::network::NetworkParams;
+
+use alloc::{borrow::Cow, vec};
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+
+/// The way the parameters of two parents are combined, see [crossover].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Crossover {
+    /// Every treshold and effect is taken from a random parent.
+    Uniform,
+    /// Every neuron is taken from a random parent, keeping a neuron's treshold
+    /// and its row of effects together.
+    NeuronBlock,
+    /// The ring of neurons is cut at a random neuron, neurons before the cut are taken
+    /// from the first parent and the others from the second.
+    OnePoint,
+    /// The ring of neurons is cut at two random neurons, neurons between the cuts are taken
+    /// from the second parent and the others from the first.
+    TwoPoint,
+}
+
+/// Combine the parameters of two parents into a new set of parameters.
+/// The result only depends on the parents, `seed` and `kind`.
+/// Input and output neurons are always taken from a random parent per index,
+/// the [Plasticity](crate::network::Plasticity) parameters from a random parent.
+/// Neuron types are taken along with the rest of a neuron, with [Crossover::Uniform]
+/// they are taken from the first parent and the effects are clamped to them.
+/// Connection masks are taken along with the effects, a parent without a mask
+/// is treated as having every connection enabled.
+/// # Panics
+/// When the parents do not have the same amount of neurons, connections,
+/// input neurons and output neurons, or when only one of them has neuron types.
+pub fn crossover(
+    a: &NetworkParams,
+    b: &NetworkParams,
+    seed: u64,
+    kind: Crossover,
+) -> NetworkParams {
+    assert_eq!(a.tresholds.len(), b.tresholds.len());
+    assert_eq!(a.effects.len(), b.effects.len());
+    assert_eq!(a.input_neurons.len(), b.input_neurons.len());
+    assert_eq!(a.output_neurons.len(), b.output_neurons.len());
+    assert_eq!(a.neuron_types.is_some(), b.neuron_types.is_some());
+
+    let masked = a.connection_mask.is_some() || b.connection_mask.is_some();
+    let (a, b) = (with_connection_mask(a, masked), with_connection_mask(b, masked));
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let neuron_count = a.tresholds.len();
+    let connection_count = a.effects.len().checked_div(neuron_count).unwrap_or(0);
+
+    let mut child = a.clone().into_owned();
+
+    match kind {
+        Crossover::Uniform => {
+            for (dst, src) in child.tresholds.iter_mut().zip(b.tresholds.iter()) {
+                if rng.gen() {
+                    *dst = *src;
+                }
+            }
+            for (i, (dst, src)) in child.effects.iter_mut().zip(b.effects.iter()).enumerate() {
+                if rng.gen() {
+                    *dst = match &child.neuron_types {
+                        Some(types) => types[i / connection_count].clamp(*src),
+                        None => *src,
+                    };
+                    if let (Some(dst_mask), Some(src_mask)) = (&mut child.connection_mask, &b.connection_mask) {
+                        dst_mask[i] = src_mask[i];
+                    }
+                }
+            }
+        }
+        Crossover::NeuronBlock => {
+            for neuron in 0..neuron_count {
+                if rng.gen() {
+                    copy_neuron(&mut child, &b, neuron, connection_count);
+                }
+            }
+        }
+        Crossover::OnePoint => {
+            let cut = rng.gen_range(0..=neuron_count);
+            for neuron in cut..neuron_count {
+                copy_neuron(&mut child, &b, neuron, connection_count);
+            }
+        }
+        Crossover::TwoPoint => {
+            let mut cuts = [rng.gen_range(0..=neuron_count), rng.gen_range(0..=neuron_count)];
+            cuts.sort_unstable();
+            for neuron in cuts[0]..cuts[1] {
+                copy_neuron(&mut child, &b, neuron, connection_count);
+            }
+        }
+    }
+
+    for (dst, src) in child.input_neurons.iter_mut().zip(b.input_neurons.iter()) {
+        if rng.gen() {
+            *dst = *src;
+        }
+    }
+    for (dst, src) in child.output_neurons.iter_mut().zip(b.output_neurons.iter()) {
+        if rng.gen() {
+            *dst = *src;
+        }
+    }
+    if rng.gen() {
+        child.plasticity = b.plasticity;
+    }
+
+    child
+}
+
+fn copy_neuron(dst: &mut NetworkParams, src: &NetworkParams, neuron: usize, connection_count: usize) {
+    dst.tresholds[neuron] = src.tresholds[neuron];
+
+    let row = neuron * connection_count..(neuron + 1) * connection_count;
+    dst.effects[row.clone()].copy_from_slice(&src.effects[row.clone()]);
+
+    if let (Some(dst_types), Some(src_types)) = (&mut dst.neuron_types, &src.neuron_types) {
+        dst_types[neuron] = src_types[neuron];
+    }
+    if let (Some(dst_mask), Some(src_mask)) = (&mut dst.connection_mask, &src.connection_mask) {
+        dst_mask[row.clone()].copy_from_slice(&src_mask[row]);
+    }
+}
+
+/// `params` with a connection mask with every connection enabled when `masked` and it has none.
+fn with_connection_mask(params: &NetworkParams, masked: bool) -> Cow<'_, NetworkParams> {
+    if masked && params.connection_mask.is_none() {
+        let mut params = params.clone();
+        params.connection_mask = Some(vec![true; params.effects.len()].into());
+        Cow::Owned(params)
+    } else {
+        Cow::Borrowed(params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{Effect, NeuronValue, Plasticity};
+
+    fn parent(value: i8, neuron_count: usize, connection_count: usize) -> NetworkParams {
+        NetworkParams {
+            tresholds: vec![NeuronValue(value as i32); neuron_count].into(),
+            effects: vec![Effect(value); neuron_count * connection_count].into(),
+            input_neurons: vec![0; 4].into(),
+            output_neurons: vec![neuron_count - 1; 4].into(),
+            plasticity: Plasticity::default(),
+            neuron_types: None,
+            connection_mask: None,
+        }
+    }
+
+    #[test]
+    fn neuron_integrity() {
+        let a = parent(1, 64, 5);
+        let b = parent(2, 64, 5);
+
+        for &kind in &[Crossover::NeuronBlock, Crossover::OnePoint, Crossover::TwoPoint] {
+            for seed in 0..16 {
+                let child = crossover(&a, &b, seed, kind);
+                assert_eq!(child, crossover(&a, &b, seed, kind));
+
+                for (treshold, row) in child.tresholds.iter().zip(child.effects.chunks(5)) {
+                    assert!(row.iter().all(|effect| effect.0 as i32 == treshold.0));
+                }
+            }
+        }
+
+        let mut ring = crossover(&a, &b, 1234, Crossover::TwoPoint).tresholds.into_vec();
+        ring.dedup();
+        assert!(ring.len() <= 3);
+    }
+
+    #[test]
+    fn uniform_mixing() {
+        let a = parent(1, 64, 5);
+        let b = parent(2, 64, 5);
+
+        let child = crossover(&a, &b, 1234, Crossover::Uniform);
+        let from_b = child.effects.iter().filter(|e| e.0 == 2).count();
+        assert!(from_b > 64 && from_b < 256);
+        assert!(child.input_neurons.iter().chain(child.output_neurons.iter()).all(|&i| i == 0 || i == 63));
+    }
+}
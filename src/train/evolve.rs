@@ -1,9 +1,19 @@
-use crate::network::{self, Effect, Network, NetworkParams, NeuronValue};
+use crate::network::{self, InitParams, Network, NetworkParams};
+use super::{crossover::{crossover, Crossover}, Evaluation};
 
 use rand::{prelude::*, distributions};
 use rand_chacha::ChaCha8Rng;
 
-use std::iter;
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::cmp::Ordering;
+#[cfg(feature = "std")]
+use std::{io, path::PathBuf};
+
+#[cfg(feature = "std")]
+mod checkpoint;
+mod structure;
+
+pub use structure::{apply_structure_mutation, insert_neuron, remove_neuron, set_connection_count, StructureMutation};
 
 /// Parameters for a noise pass, see [build_network_from_noise].
 #[derive(Debug, Clone, Copy)]
@@ -32,13 +42,105 @@ pub fn apply_parameter_noise(
     seed: u64,
     power: u8,
 ) {
-    const IO_NEURON_OFFSET_DIVISOR: isize = 4;
+    apply_masked_parameter_noise(params, seed, &NoiseMask::all(power));
+}
+
+/// Selects which parameters a noise pass changes and with which power,
+/// see [apply_masked_parameter_noise].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoiseMask {
+    /// Power of the noise on the effects, `None` leaves them unchanged.
+    pub effect_power: Option<u8>,
+    /// Power of the noise on the tresholds, `None` leaves them unchanged.
+    pub treshold_power: Option<u8>,
+    /// Power of the noise on the input and output neurons, `None` leaves them unchanged.
+    pub io_power: Option<u8>,
+    /// Power of the noise on the [Plasticity](network::Plasticity) parameters, `None` leaves them unchanged.
+    /// They are never changed when plasticity is not enabled.
+    pub plasticity_power: Option<u8>,
+    /// When set, only the tresholds and effects of neurons for which this is `true` are changed.
+    pub neurons: Option<Box<[bool]>>,
+    /// Every parameter is changed with a probability of `ratio.0 / ratio.1`.
+    pub ratio: (u32, u32),
+    /// When `true`, a changed input or output neuron never ends up on a neuron that is already used
+    /// by another input or output (respectively), so unique indices stay unique.
+    pub unique_io: bool,
+    /// When set, every connection is enabled or disabled with a probability of `toggle.0 / toggle.1`,
+    /// regardless of `ratio` but only for the neurons selected by `neurons`.
+    /// See [NetworkParams::set_connection_enabled].
+    pub connection_toggle: Option<(u32, u32)>,
+}
+
+impl NoiseMask {
+    /// A mask that changes every parameter with the same power,
+    /// the same as [apply_parameter_noise].
+    pub fn all(power: u8) -> Self {
+        Self {
+            effect_power: Some(power),
+            treshold_power: Some(power),
+            io_power: Some(power),
+            plasticity_power: Some(power),
+            neurons: None,
+            ratio: (1, 1),
+            unique_io: false,
+            connection_toggle: None,
+        }
+    }
+
+    /// A mask that changes nothing, to be used as a base for more specific masks.
+    /// # Examples
+    /// ```
+    /// # use siarne::train::evolve::NoiseMask;
+    /// // only change the tresholds of the first 2 of 4 neurons
+    /// let mask = NoiseMask {
+    ///     treshold_power: Some(1),
+    ///     neurons: Some(vec![true, true, false, false].into()),
+    ///     ..NoiseMask::none()
+    /// };
+    /// ```
+    pub fn none() -> Self {
+        Self {
+            effect_power: None,
+            treshold_power: None,
+            io_power: None,
+            plasticity_power: None,
+            neurons: None,
+            ratio: (1, 1),
+            unique_io: false,
+            connection_toggle: None,
+        }
+    }
+}
+
+/// Apply noise to the parameters selected by `mask`, see [apply_parameter_noise].
+/// A parameter receives the same noise regardless of which other parameters are selected,
+/// given the same `seed` and power.
+/// # Panics
+/// When `mask.neurons` does not have an element for every neuron, when `mask.ratio`
+/// or `mask.connection_toggle` is not a valid probability, when `mask.unique_io` is set
+/// and there are more inputs or outputs than neurons, or when there are no neurons
+/// but there are effects, inputs or outputs.
+pub fn apply_masked_parameter_noise(
+    params: &mut NetworkParams,
+    seed: u64,
+    mask: &NoiseMask,
+) {
+    const IO_NEURON_OFFSET_DIVISOR: i64 = 4;
+
+    let neuron_count = params.tresholds.len();
+    assert!(
+        neuron_count > 0
+            || (params.effects.is_empty() && params.input_neurons.is_empty() && params.output_neurons.is_empty())
+    );
+    if let Some(neurons) = &mask.neurons {
+        assert_eq!(neurons.len(), neuron_count);
+    }
 
     let mut rng = ChaCha8Rng::seed_from_u64(seed);
     let dist = distributions::Uniform::from(u64::MIN..=u64::MAX);
-    let p = power as u64;
 
-    let mut offset = move || -> i64 {
+    let mut offset = move |power: u8| -> i64 {
+        let p = power as u64;
         let r = dist.sample(&mut rng);
         // for a large part of the domain, this will produce values close to 0
         // also, it is unlikely to skip values at least at reasonable powers
@@ -49,74 +151,107 @@ pub fn apply_parameter_noise(
         
         (unsigned / 2) as i64 * sign
     };
-    
-    for effect in params.effects.iter_mut() {
-        let noise = offset()
+
+    // selection uses a separate stream so the noise itself does not depend on it
+    let mut select_rng = ChaCha8Rng::seed_from_u64(seed);
+    select_rng.set_stream(1);
+    let ratio = distributions::Bernoulli::from_ratio(mask.ratio.0, mask.ratio.1).unwrap();
+    let full_ratio = mask.ratio.0 == mask.ratio.1;
+    let mut select = move |neuron: Option<usize>| -> bool {
+        let neuron_selected = match (&mask.neurons, neuron) {
+            (Some(neurons), Some(neuron)) => neurons[neuron],
+            _ => true,
+        };
+        neuron_selected && (full_ratio || ratio.sample(&mut select_rng))
+    };
+
+    let connection_count = params.effects.len().checked_div(neuron_count).unwrap_or(0);
+    for (i, effect) in params.effects.iter_mut().enumerate() {
+        let noise = offset(mask.effect_power.unwrap_or(0))
             .clamp(i8::MIN as i64, i8::MAX as i64) as i8;
-        // saturating add because a small offset should never cause a huge difference in
-        // the parameter value
-        effect.0 = effect.0.saturating_add(noise);
+        let enabled = params.connection_mask.as_ref().is_none_or(|mask| mask[i]);
+        if mask.effect_power.is_some() && select(Some(i / connection_count)) && enabled {
+            // saturating add because a small offset should never cause a huge difference in
+            // the parameter value
+            effect.0 = effect.0.saturating_add(noise);
+            if let Some(types) = &params.neuron_types {
+                *effect = types[i / connection_count].clamp(*effect);
+            }
+        }
     }
 
-    for treshold in params.tresholds.iter_mut() {
-        let noise = offset()
+    for (i, treshold) in params.tresholds.iter_mut().enumerate() {
+        let noise = offset(mask.treshold_power.unwrap_or(0))
             .clamp(i32::MIN as i64, i32::MAX as i64) as i32;
-        treshold.0 = treshold.0.saturating_add(noise);
+        if mask.treshold_power.is_some() && select(Some(i)) {
+            treshold.0 = treshold.0.saturating_add(noise);
+        }
     }
 
-    let neuron_count = params.tresholds.len();
-    for input_neuron in params.input_neurons.iter_mut() {
-        let noise = offset()
-            .clamp(isize::MIN as i64, isize::MAX as i64) as isize
-            / IO_NEURON_OFFSET_DIVISOR;
-        
-        // TODO: what if this overflows?
-        if noise < 0 {
-            *input_neuron = (*input_neuron + ((noise * -1) as usize % neuron_count)) % neuron_count;
-        } else {
-            *input_neuron = (*input_neuron + (noise as usize % neuron_count)) % neuron_count;
-        }
-    }
-    
-    for output_neuron in params.output_neurons.iter_mut() {
-        let noise = offset()
-            .clamp(isize::MIN as i64, isize::MAX as i64) as isize
-            / IO_NEURON_OFFSET_DIVISOR;
-        
-        if noise < 0 {
-            *output_neuron = (*output_neuron + ((noise * -1) as usize % neuron_count)) % neuron_count;
-        } else {
-            *output_neuron = (*output_neuron + (noise as usize % neuron_count)) % neuron_count;
-        }
-    }
-
-    // let tmp: Vec<_> = std::iter::repeat_with(|| offset())
-    //     .take(655360)
-    //     .collect();
-    
-    // println!("min: {:?}, max: {:?}", tmp.iter().min(), tmp.iter().max());
-    // println!("ratio {:.6}", tmp.iter().filter(|&&t| t < 0).count() as f64 / tmp.iter().filter(|&&t| t > 0).count() as f64);
-    // for i in 0..5 {
-    //     let positive = tmp.iter()
-    //         .filter(|&&t| t == i)
-    //         .count();
-    //     
-    //     println!(" {}: {} {:.6}", i, positive, positive as f64 / tmp.len() as f64);
-    //     if i != 0 {
-    //         let negative = tmp.iter()
-    //             .filter(|&&t| t == -i)
-    //             .count();
-    //     
-    //         println!("{}: {} {:.6}", -i, negative, negative as f64 / tmp.len() as f64);
-    //     }
-    // }
+    let io_power = mask.io_power.unwrap_or(0);
+    for neurons in [&mut params.input_neurons, &mut params.output_neurons] {
+        if mask.unique_io {
+            assert!(neurons.len() <= neuron_count);
+        }
+
+        for i in 0..neurons.len() {
+            let noise = offset(io_power) / IO_NEURON_OFFSET_DIVISOR;
+            if mask.io_power.is_none() || !select(None) {
+                continue;
+            }
+
+            neurons[i] = shift_neuron(neurons[i], noise, neuron_count);
+            if mask.unique_io {
+                // keep moving in the same direction until the neuron is free
+                let step = if noise < 0 { -1 } else { 1 };
+                while neurons.iter().enumerate().any(|(j, &n)| j != i && n == neurons[i]) {
+                    neurons[i] = shift_neuron(neurons[i], step, neuron_count);
+                }
+            }
+        }
+    }
+
+    if params.plasticity.enabled {
+        let plasticity = &mut params.plasticity;
+        let mut rates = vec![&mut plasticity.potentiation, &mut plasticity.depression];
+        if plasticity.reward_modulated {
+            rates.push(&mut plasticity.trace_decay);
+            rates.push(&mut plasticity.reward_shift);
+        }
+        for rate in rates {
+            let noise = offset(mask.plasticity_power.unwrap_or(0));
+            if mask.plasticity_power.is_some() && select(None) {
+                *rate = (*rate as i64 + noise).clamp(u8::MIN as i64, u8::MAX as i64) as u8;
+            }
+        }
+    }
+
+    if let Some(toggle) = mask.connection_toggle {
+        let mut toggle_rng = ChaCha8Rng::seed_from_u64(seed);
+        toggle_rng.set_stream(2);
+        let toggle = distributions::Bernoulli::from_ratio(toggle.0, toggle.1).unwrap();
+
+        for i in 0..params.effects.len() {
+            let neuron_selected = mask.neurons.as_ref().is_none_or(|neurons| neurons[i / connection_count]);
+            if toggle.sample(&mut toggle_rng) && neuron_selected {
+                let enabled = params.connection_mask.as_ref().is_none_or(|mask| mask[i]);
+                params.set_connection_enabled(i, !enabled);
+            }
+        }
+    }
+}
+
+/// Move `neuron` by `offset` positions along the ring of neurons.
+fn shift_neuron(neuron: usize, offset: i64, neuron_count: usize) -> usize {
+    (neuron as i128 + offset as i128).rem_euclid(neuron_count as i128) as usize
 }
 
 /// Constructs a [Network] by generating initial parameters with `seed`,
 /// then applying the specified `passes` of noise.  
 /// See [apply_parameter_noise] for more information.
+/// The initial parameters are the same as those of [Network::from_seed].
 /// # Errors
-/// See [Network::new]. 
+/// See [Network::from_seed].
 pub fn build_network_from_noise<Is>(
     neuron_count: usize,
     connection_count: usize,
@@ -128,35 +263,14 @@ pub fn build_network_from_noise<Is>(
 where
     Is: Iterator<Item = NoisePassParams>
 {
-    let mut rng = ChaCha8Rng::seed_from_u64(seed);
-
-    let tresholds = iter::repeat_with(|| NeuronValue(rng.gen()))
-        .take(neuron_count)
-        .collect();
-    
-    let effect_count = neuron_count
-        .checked_mul(connection_count)
-        .ok_or(network::Error::EffectCountOverflow)?;
-
-    let effects = iter::repeat_with(|| Effect(rng.gen()))
-        .take(effect_count)
-        .collect();
-    
-    let neuron_dist = distributions::Uniform::from(0..neuron_count);
-    
-    let input_neurons = iter::repeat_with(|| neuron_dist.sample(&mut rng))
-        .take(input_count)
-        .collect();
-    let output_neurons = iter::repeat_with(|| neuron_dist.sample(&mut rng))
-        .take(output_count)
-        .collect();
-
-    let mut params = NetworkParams {
-        tresholds,
-        effects,
-        input_neurons,
-        output_neurons,
-    };
+    let mut params = NetworkParams::random(
+        neuron_count,
+        connection_count,
+        input_count,
+        output_count,
+        &InitParams::default(),
+        &mut ChaCha8Rng::seed_from_u64(seed),
+    )?;
 
     for pass in passes {
         apply_parameter_noise(&mut params, pass.seed, pass.power);
@@ -165,9 +279,448 @@ where
     Network::with_params(params)
 }
 
+/// Parameters for an [Evolution] run.
+#[derive(Debug, Clone, Copy)]
+pub struct EvolutionParams {
+    /// Amount of individuals in each generation.
+    pub population_size: usize,
+    /// Amount of best individuals that survive a generation and produce the offspring.
+    pub elite_count: usize,
+    /// How two elites are combined into a child, `None` only mutates a single elite.
+    pub crossover: Option<Crossover>,
+    /// Power of the noise applied to every child, see [apply_parameter_noise].
+    pub power: PowerSchedule,
+    /// Probability of toggling each connection of a child, see [NoiseMask::connection_toggle].
+    pub connection_toggle: Option<(u32, u32)>,
+    /// Keep the input and output neurons of every child unique, see [NoiseMask::unique_io].
+    /// Structural mutations keep them unique as well.
+    pub unique_io: bool,
+    /// Structural mutations applied to every child after the noise, see [apply_structure_mutation].
+    /// Parents with a different amount of neurons or connections are not combined by crossover,
+    /// the child is a copy of the first parent instead.
+    pub structure: Option<StructureMutation>,
+    /// Select on a blend of fitness and novelty instead of fitness only, see [Novelty].
+    pub novelty: Option<Novelty>,
+}
+
+/// Novelty search parameters for an [Evolution] run.
+/// The novelty of an individual is the mean distance of its behaviour to the `k` nearest behaviours
+/// of the rest of the population and the archive, where the distance is the sum of the absolute
+/// differences of the behaviour values. Newly evaluated individuals are randomly added to the archive,
+/// so behaviours that have been seen before become less novel over time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Novelty {
+    /// Amount of nearest behaviours the novelty is measured against.
+    pub k: usize,
+    /// Weight of the novelty in selection from 0, fitness only, to 1, novelty only.
+    /// Fitness and novelty are both scaled to `0..=1` over the population before blending.
+    pub weight: f64,
+    /// Probability of adding the behaviour of a newly evaluated individual to the archive.
+    pub archive_ratio: (u32, u32),
+    /// Maximum amount of behaviours in the archive, the oldest are removed first.
+    pub archive_size: usize,
+}
+
+impl Novelty {
+    /// Whether [Evolution::new] accepts these parameters.
+    fn is_valid(&self) -> bool {
+        self.k > 0
+            && (0.0..=1.0).contains(&self.weight)
+            && distributions::Bernoulli::from_ratio(self.archive_ratio.0, self.archive_ratio.1).is_ok()
+    }
+}
+
+/// Determines the power of the noise applied to the children in an [Evolution] run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerSchedule {
+    /// The same power every generation.
+    Fixed(u8),
+    /// The power goes linearly from `start` to `end` in `generations` generations,
+    /// after which it stays at `end`.
+    Decay { start: u8, end: u8, generations: u64 },
+    /// The 1/5th success rule: when more than a fifth of the children of a generation
+    /// are better than their first parent the power is increased by 1, when less it is decreased by 1.
+    OneFifth { initial: u8, min: u8, max: u8 },
+    /// Every individual carries its own power, which a child inherits from its first parent
+    /// and randomly changes by at most 1 before using it.
+    SelfAdaptive { initial: u8, min: u8, max: u8 },
+}
+
+impl PowerSchedule {
+    fn initial(&self) -> u8 {
+        match *self {
+            Self::Fixed(power) => power,
+            Self::Decay { start, .. } => start,
+            Self::OneFifth { initial, .. } | Self::SelfAdaptive { initial, .. } => initial,
+        }
+    }
+}
+
+/// Where and how often [Evolution::run] writes checkpoints, see [Evolution::save_checkpoint].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    pub path: PathBuf,
+    /// A checkpoint is written after every generation that is a multiple of this.
+    pub interval: u64,
+}
+
+/// A member of the population of an [Evolution] run.
+#[derive(Debug, Clone)]
+pub struct Individual {
+    pub params: NetworkParams,
+    /// `None` until the individual has been evaluated.
+    pub fitness: Option<f64>,
+    /// The fitness of the first parent, `None` for the initial population and for elites
+    /// that survived a generation, so only new children count for [PowerSchedule::OneFifth].
+    pub parent_fitness: Option<f64>,
+    /// The power this individual's children are mutated with when using [PowerSchedule::SelfAdaptive].
+    pub power: u8,
+    /// The behaviour of the last evaluation, see [Evaluation::behaviour].
+    pub behaviour: Vec<f64>,
+    /// The novelty of the last generation, `None` until selected with [Novelty].
+    pub novelty: Option<f64>,
+}
+
+/// An elitist evolution strategy.
+/// Each generation the population is evaluated, the best individuals are kept and the rest
+/// of the population is replaced by mutated (and possibly recombined) copies of them.
+/// All randomness is derived from the seed passed to [Evolution::new], so two runs with the same
+/// seed, initial population and deterministic fitness function produce the same generations.
+pub struct Evolution {
+    params: EvolutionParams,
+    seed: u64,
+    generation: u64,
+    power: u8,
+    population: Vec<Individual>,
+    best_fitness_history: Vec<f64>,
+    /// Behaviours for [Novelty], oldest first.
+    archive: Vec<Vec<f64>>,
+}
+
+impl Evolution {
+    /// Start an evolution run from the specified initial population, which may be smaller
+    /// than the population size.
+    /// # Panics
+    /// When `elite_count` is 0 or greater than `population_size`, when `population` is empty,
+    /// or when using [Novelty] with a `k` of 0, a `weight` outside of `0..=1` or an `archive_ratio`
+    /// that is not a valid probability.
+    pub fn new(params: EvolutionParams, seed: u64, population: Vec<NetworkParams>) -> Self {
+        assert!(params.elite_count > 0 && params.elite_count <= params.population_size);
+        assert!(!population.is_empty());
+        assert!(params.novelty.is_none_or(|novelty| novelty.is_valid()));
+
+        let power = params.power.initial();
+        let population = population
+            .into_iter()
+            .map(|params| Individual {
+                params,
+                fitness: None,
+                parent_fitness: None,
+                power,
+                behaviour: vec![],
+                novelty: None,
+            })
+            .collect();
+
+        Self {
+            params,
+            seed,
+            generation: 0,
+            power,
+            population,
+            best_fitness_history: vec![],
+            archive: vec![],
+        }
+    }
+
+    /// Evaluate the individuals that have no fitness yet, higher is better,
+    /// then replace the population with the next generation.
+    /// # Panics
+    /// When using [Novelty], which needs the behaviours passed to [Evolution::step_evaluated].
+    pub fn step<F>(&mut self, mut fitness: F)
+    where
+        F: FnMut(&NetworkParams) -> f64,
+    {
+        assert!(self.params.novelty.is_none(), "novelty search needs behaviours, use step_evaluated");
+        self.step_evaluated(|params| Evaluation { fitness: fitness(params), behaviour: vec![] });
+    }
+
+    /// Like [Evolution::step], but `evaluate` also characterises the behaviour for [Novelty].
+    /// # Panics
+    /// When using [Novelty] and a behaviour is empty or not every behaviour has the same length.
+    pub fn step_evaluated<F>(&mut self, mut evaluate: F)
+    where
+        F: FnMut(&NetworkParams) -> Evaluation,
+    {
+        let mut evaluated = vec![false; self.population.len()];
+        for (individual, evaluated) in self.population.iter_mut().zip(evaluated.iter_mut()) {
+            if individual.fitness.is_none() {
+                let evaluation = evaluate(&individual.params);
+                individual.fitness = Some(evaluation.fitness);
+                individual.behaviour = evaluation.behaviour;
+                *evaluated = true;
+            }
+        }
+        self.adapt_power();
+
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        rng.set_stream(self.generation);
+
+        let best_fitness = self.population
+            .iter()
+            .map(|i| i.fitness.unwrap())
+            .max_by(f64::total_cmp)
+            .unwrap();
+        self.best_fitness_history.push(best_fitness);
+
+        match self.params.novelty {
+            Some(novelty) => {
+                self.update_novelty(&novelty);
+                let scores = self.novelty_scores(novelty.weight);
+                self.archive_behaviours(&novelty, &evaluated, &mut rng);
+
+                // stable so ties are broken deterministically by position
+                let mut ranked: Vec<_> = self.population.drain(..).zip(scores).collect();
+                ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+                self.population = ranked.into_iter().map(|(individual, _)| individual).collect();
+            }
+            None => self.population.sort_by(|a, b| b.fitness.unwrap().total_cmp(&a.fitness.unwrap())),
+        }
+        self.population.truncate(self.params.elite_count);
+        for elite in self.population.iter_mut() {
+            elite.parent_fitness = None;
+        }
+
+        let elite_count = self.population.len();
+        let elite_dist = distributions::Uniform::from(0..elite_count);
+        while self.population.len() < self.params.population_size {
+            let a = elite_dist.sample(&mut rng);
+            let parent_fitness = self.population[a].fitness;
+            let power = match self.params.power {
+                PowerSchedule::SelfAdaptive { min, max, .. } => {
+                    let power = self.population[a].power as i16 + rng.gen_range(-1..=1);
+                    power.clamp(min as i16, max as i16) as u8
+                }
+                _ => self.power,
+            };
+            let mut params = match self.params.crossover {
+                Some(kind) if elite_count > 1 => {
+                    // pick a distinct second parent
+                    let b = (a + 1 + rng.gen_range(0..elite_count - 1)) % elite_count;
+                    let (a, b) = (&self.population[a].params, &self.population[b].params);
+                    let seed = rng.gen();
+                    if a.tresholds.len() == b.tresholds.len() && a.effects.len() == b.effects.len() {
+                        crossover(a, b, seed, kind)
+                    } else {
+                        a.clone()
+                    }
+                }
+                _ => self.population[a].params.clone(),
+            };
+            let mask = NoiseMask {
+                connection_toggle: self.params.connection_toggle,
+                unique_io: self.params.unique_io,
+                ..NoiseMask::all(power)
+            };
+            apply_masked_parameter_noise(&mut params, rng.gen(), &mask);
+            if let Some(structure) = &self.params.structure {
+                apply_structure_mutation(&mut params, rng.gen(), structure);
+            }
+
+            self.population.push(Individual {
+                params,
+                fitness: None,
+                parent_fitness,
+                power,
+                behaviour: vec![],
+                novelty: None,
+            });
+        }
+
+        self.generation += 1;
+    }
+
+    /// Step until `generations` generations have been completed, writing a checkpoint
+    /// periodically and after the last generation.
+    /// # Errors
+    /// When writing a checkpoint fails.
+    /// # Panics
+    /// When using [Novelty], see [Evolution::step].
+    #[cfg(feature = "std")]
+    pub fn run<F>(&mut self, generations: u64, mut fitness: F, checkpoint: Option<&Checkpoint>) -> io::Result<()>
+    where
+        F: FnMut(&NetworkParams) -> f64,
+    {
+        self.run_evaluated(
+            generations,
+            |params| Evaluation { fitness: fitness(params), behaviour: vec![] },
+            checkpoint,
+        )
+    }
+
+    /// Like [Evolution::run], but `evaluate` also characterises the behaviour for [Novelty],
+    /// see [Evolution::step_evaluated].
+    /// # Errors
+    /// When writing a checkpoint fails.
+    #[cfg(feature = "std")]
+    pub fn run_evaluated<F>(
+        &mut self,
+        generations: u64,
+        mut evaluate: F,
+        checkpoint: Option<&Checkpoint>,
+    ) -> io::Result<()>
+    where
+        F: FnMut(&NetworkParams) -> Evaluation,
+    {
+        while self.generation < generations {
+            self.step_evaluated(&mut evaluate);
+
+            if let Some(checkpoint) = checkpoint {
+                let periodic = self.generation.checked_rem(checkpoint.interval) == Some(0);
+                if periodic || self.generation == generations {
+                    self.save_checkpoint(&checkpoint.path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Measure the novelty of every individual against the rest of the population and the archive.
+    fn update_novelty(&mut self, novelty: &Novelty) {
+        let behaviour_len = self.population[0].behaviour.len();
+        assert!(behaviour_len > 0, "novelty search needs behaviours");
+        assert!(self.population.iter().all(|i| i.behaviour.len() == behaviour_len));
+        assert!(self.archive.iter().all(|b| b.len() == behaviour_len));
+
+        let mut distances = vec![];
+        for i in 0..self.population.len() {
+            let behaviour = &self.population[i].behaviour;
+            distances.clear();
+            distances.extend(
+                self.population
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .map(|(_, other)| &other.behaviour)
+                    .chain(self.archive.iter())
+                    .map(|other| behaviour_distance(behaviour, other)),
+            );
+            distances.sort_unstable_by(f64::total_cmp);
+
+            let k = novelty.k.min(distances.len());
+            let value = if k == 0 { 0.0 } else { distances[..k].iter().sum::<f64>() / k as f64 };
+            self.population[i].novelty = Some(value);
+        }
+    }
+
+    /// Blend the fitness and novelty of every individual, both scaled to `0..=1`.
+    fn novelty_scores(&self, weight: f64) -> Vec<f64> {
+        let fitness = normalized(self.population.iter().map(|i| i.fitness.unwrap()));
+        let novelty = normalized(self.population.iter().map(|i| i.novelty.unwrap()));
+        fitness
+            .into_iter()
+            .zip(novelty)
+            .map(|(f, n)| (1.0 - weight) * f + weight * n)
+            .collect()
+    }
+
+    fn archive_behaviours(&mut self, novelty: &Novelty, evaluated: &[bool], rng: &mut ChaCha8Rng) {
+        let ratio = distributions::Bernoulli::from_ratio(novelty.archive_ratio.0, novelty.archive_ratio.1).unwrap();
+        for (individual, &evaluated) in self.population.iter().zip(evaluated.iter()) {
+            if evaluated && ratio.sample(rng) {
+                self.archive.push(individual.behaviour.clone());
+            }
+        }
+
+        let excess = self.archive.len().saturating_sub(novelty.archive_size);
+        self.archive.drain(..excess);
+    }
+
+    fn adapt_power(&mut self) {
+        match self.params.power {
+            PowerSchedule::Fixed(_) | PowerSchedule::SelfAdaptive { .. } => (),
+            PowerSchedule::Decay { start, end, generations } => {
+                let done = self.generation.min(generations) as i64;
+                let range = end as i64 - start as i64;
+                self.power = (start as i64 + (range * done).checked_div(generations as i64).unwrap_or(range)) as u8;
+            }
+            PowerSchedule::OneFifth { min, max, .. } => {
+                let (children, successes) = self.population
+                    .iter()
+                    .filter_map(|i| Some((i.fitness?, i.parent_fitness?)))
+                    .fold((0, 0), |(c, s), (f, p)| (c + 1, s + (f > p) as usize));
+
+                if children == 0 {
+                    return;
+                }
+                match (successes * 5).cmp(&children) {
+                    Ordering::Greater => self.power = self.power.saturating_add(1).min(max),
+                    Ordering::Less => self.power = self.power.saturating_sub(1).max(min),
+                    Ordering::Equal => (),
+                }
+            }
+        }
+    }
+
+    /// The power used for the children of the next generation.
+    /// Meaningless with [PowerSchedule::SelfAdaptive], where every individual carries its own
+    /// [Individual::power] and this stays the initial power.
+    pub fn power(&self) -> u8 {
+        self.power
+    }
+
+    /// The amount of completed calls to [Evolution::step].
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The current population, elites of the last generation first.
+    pub fn population(&self) -> &[Individual] {
+        &self.population
+    }
+
+    /// The best individual of the last generation, if any generation has been evaluated.
+    /// With [Novelty] this is the individual with the best blend of fitness and novelty.
+    pub fn best(&self) -> Option<&Individual> {
+        self.population.first().filter(|i| i.fitness.is_some())
+    }
+
+    /// The highest fitness of every generation so far.
+    pub fn best_fitness_history(&self) -> &[f64] {
+        &self.best_fitness_history
+    }
+
+    /// The behaviours that novelty is measured against besides the population, oldest first.
+    pub fn archive(&self) -> &[Vec<f64>] {
+        &self.archive
+    }
+}
+
+fn behaviour_distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| if a > b { a - b } else { b - a })
+        .sum()
+}
+
+/// Scale `values` to `0..=1`, or to 0 when they are all the same.
+fn normalized<I: Iterator<Item = f64> + Clone>(values: I) -> Vec<f64> {
+    let (min, max) = values
+        .clone()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| (min.min(v), max.max(v)));
+    let range = max - min;
+    values
+        .map(|v| if range > 0.0 { (v - min) / range } else { 0.0 })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::network::{Effect, NeuronValue, Plasticity};
+    use core::iter;
 
     #[test]
     fn noise_determinism() {
@@ -223,16 +776,16 @@ mod tests {
         assert_eq!(
             net.params().input_neurons.as_ref(),
             &[
-                15,
-                9,
-                4,
                 3,
+                9,
+                0,
                 5,
+                15,
                 14,
-                0,
+                12,
+                4,
+                11,
                 6,
-                3,
-                8,
             ],
         );
 
@@ -240,16 +793,457 @@ mod tests {
             net.params().output_neurons.as_ref(),
             &[
                 12,
-                8,
-                2,
-                12,
-                14,
-                11,
-                14,
+                0,
+                6,
                 14,
-                11,
-                2,
+                6,
+                1,
+                8,
+                4,
+                3,
+                6,
             ],
         );
     }
+
+    #[test]
+    fn evolution_determinism() {
+        let params = EvolutionParams {
+            population_size: 8,
+            elite_count: 3,
+            crossover: Some(Crossover::NeuronBlock),
+            power: PowerSchedule::Fixed(2),
+            connection_toggle: None,
+            unique_io: false,
+            structure: None,
+            novelty: None,
+        };
+        let initial = build_network_from_noise(16, 3, 2, 2, 1234, iter::empty())
+            .unwrap()
+            .extract_params();
+        // reward networks with low tresholds
+        let fitness = |p: &NetworkParams| -p.tresholds.iter().map(|t| t.0 as f64).sum::<f64>();
+
+        let mut a = Evolution::new(params, 1234, vec![initial.clone()]);
+        let mut b = Evolution::new(params, 1234, vec![initial]);
+        for _ in 0..20 {
+            a.step(fitness);
+            b.step(fitness);
+        }
+
+        assert_eq!(a.generation(), 20);
+        assert_eq!(a.population().len(), 8);
+        assert_eq!(a.best_fitness_history(), b.best_fitness_history());
+        assert_eq!(a.best().unwrap().params, b.best().unwrap().params);
+        assert!(a.best_fitness_history().windows(2).all(|w| w[1] >= w[0]));
+    }
+
+    #[test]
+    fn power_schedules() {
+        let mut params = EvolutionParams {
+            population_size: 8,
+            elite_count: 2,
+            crossover: None,
+            power: PowerSchedule::Decay { start: 10, end: 2, generations: 4 },
+            connection_toggle: None,
+            unique_io: false,
+            structure: None,
+            novelty: None,
+        };
+        let initial = build_network_from_noise(16, 3, 0, 0, 1234, iter::empty())
+            .unwrap()
+            .extract_params();
+
+        let mut evolution = Evolution::new(params, 1234, vec![initial.clone()]);
+        let mut powers = vec![];
+        for _ in 0..6 {
+            evolution.step(|_| 0.0);
+            powers.push(evolution.power());
+        }
+        assert_eq!(powers, [10, 8, 6, 4, 2, 2]);
+
+        // children are never better than their parents, so the power goes down to the minimum
+        params.power = PowerSchedule::OneFifth { initial: 5, min: 1, max: 8 };
+        let mut evolution = Evolution::new(params, 1234, vec![initial.clone()]);
+        for _ in 0..10 {
+            evolution.step(|_| 0.0);
+        }
+        assert_eq!(evolution.power(), 1);
+
+        // every child of the initial parameters is better, after which the surviving elite
+        // must not be counted as a successful child again
+        params.power = PowerSchedule::OneFifth { initial: 5, min: 1, max: 8 };
+        params.population_size = 5;
+        params.elite_count = 1;
+        let mut evolution = Evolution::new(params, 1234, vec![initial.clone()]);
+        let mut powers = vec![];
+        for _ in 0..5 {
+            evolution.step(|p: &NetworkParams| if *p == initial { 0.0 } else { 1.0 });
+            powers.push(evolution.power());
+        }
+        assert_eq!(powers, [5, 6, 5, 4, 3]);
+
+        params.power = PowerSchedule::SelfAdaptive { initial: 5, min: 3, max: 6 };
+        let mut evolution = Evolution::new(params, 1234, vec![initial]);
+        for _ in 0..10 {
+            evolution.step(|p| p.effects.iter().map(|e| e.0 as f64).sum());
+        }
+        assert!(evolution.population().iter().all(|i| (3..=6).contains(&i.power)));
+    }
+
+    #[test]
+    fn masked_noise() {
+        let original = build_network_from_noise(16, 3, 4, 4, 1234, iter::empty())
+            .unwrap()
+            .extract_params();
+        let mut full = original.clone();
+        apply_parameter_noise(&mut full, 1234, 3);
+
+        // frozen first half, only tresholds and effects
+        let mut neurons = vec![false; 16];
+        neurons[8..].fill(true);
+        let mask = NoiseMask {
+            effect_power: Some(3),
+            treshold_power: Some(3),
+            neurons: Some(neurons.into()),
+            ..NoiseMask::none()
+        };
+        let mut masked = original.clone();
+        apply_masked_parameter_noise(&mut masked, 1234, &mask);
+
+        assert_eq!(masked.tresholds[..8], original.tresholds[..8]);
+        assert_eq!(masked.tresholds[8..], full.tresholds[8..]);
+        assert_eq!(masked.effects[..8 * 3], original.effects[..8 * 3]);
+        assert_eq!(masked.effects[8 * 3..], full.effects[8 * 3..]);
+        assert_eq!(masked.input_neurons, original.input_neurons);
+        assert_eq!(masked.output_neurons, original.output_neurons);
+
+        let mask = NoiseMask {
+            effect_power: Some(3),
+            ratio: (1, 4),
+            ..NoiseMask::none()
+        };
+        let mut sparse = original.clone();
+        apply_masked_parameter_noise(&mut sparse, 1234, &mask);
+        let changed = sparse.effects
+            .iter()
+            .zip(original.effects.iter())
+            .filter(|(a, b)| a != b)
+            .count();
+        let changed_full = full.effects
+            .iter()
+            .zip(original.effects.iter())
+            .filter(|(a, b)| a != b)
+            .count();
+        assert!(changed > 0 && changed < changed_full);
+        assert_eq!(sparse.tresholds, original.tresholds);
+    }
+
+    #[test]
+    fn io_noise_symmetry() {
+        const NEURON_COUNT: usize = 101;
+        let mask = NoiseMask {
+            io_power: Some(255),
+            ..NoiseMask::none()
+        };
+
+        let mut forward = 0i32;
+        let mut backward = 0i32;
+        for seed in 0..1000 {
+            let mut params = NetworkParams {
+                tresholds: vec![NeuronValue(0); NEURON_COUNT].into(),
+                effects: vec![Effect(0); NEURON_COUNT].into(),
+                input_neurons: vec![50; 8].into(),
+                output_neurons: vec![].into(),
+                plasticity: Plasticity::default(),
+                neuron_types: None,
+                connection_mask: None,
+            };
+            apply_masked_parameter_noise(&mut params, seed, &mask);
+
+            for &neuron in params.input_neurons.iter() {
+                match neuron.cmp(&50) {
+                    Ordering::Greater => forward += 1,
+                    Ordering::Less => backward += 1,
+                    Ordering::Equal => (),
+                }
+            }
+        }
+
+        assert!(forward + backward > 1000);
+        assert!((forward - backward).abs() < (forward + backward) / 10);
+    }
+
+    #[test]
+    fn unique_io() {
+        let mask = NoiseMask {
+            io_power: Some(255),
+            unique_io: true,
+            ..NoiseMask::none()
+        };
+
+        for seed in 0..100 {
+            let mut params = NetworkParams {
+                tresholds: vec![NeuronValue(0); 8].into(),
+                effects: vec![Effect(0); 8].into(),
+                input_neurons: (0..8).collect(),
+                output_neurons: vec![1, 3, 5].into(),
+                plasticity: Plasticity::default(),
+                neuron_types: None,
+                connection_mask: None,
+            };
+            apply_masked_parameter_noise(&mut params, seed, &mask);
+
+            let mut inputs = params.input_neurons.to_vec();
+            inputs.sort_unstable();
+            assert_eq!(inputs, (0..8).collect::<Vec<_>>());
+
+            let mut outputs = params.output_neurons.to_vec();
+            outputs.sort_unstable();
+            outputs.dedup();
+            assert_eq!(outputs.len(), 3);
+        }
+    }
+
+    #[test]
+    fn neuron_types() {
+        let init = InitParams { excitatory: Some((1, 2)), ..InitParams::default() };
+        let mut rng = ChaCha8Rng::seed_from_u64(1234);
+        let a = NetworkParams::random(32, 5, 2, 2, &init, &mut rng).unwrap();
+        let mut b = NetworkParams::random(32, 5, 2, 2, &init, &mut rng).unwrap();
+
+        for seed in 0..16 {
+            apply_parameter_noise(&mut b, seed, 200);
+        }
+        assert!(Network::with_params(b.clone()).is_ok());
+
+        for &kind in &[Crossover::Uniform, Crossover::NeuronBlock, Crossover::OnePoint, Crossover::TwoPoint] {
+            assert!(Network::with_params(crossover(&a, &b, 1234, kind)).is_ok());
+        }
+    }
+
+    #[test]
+    fn connection_toggle() {
+        let a = build_network_from_noise(64, 8, 2, 2, 1234, iter::empty()).unwrap().extract_params();
+        let mut b = a.clone();
+
+        let mask = NoiseMask {
+            connection_toggle: Some((1, 4)),
+            ..NoiseMask::all(1)
+        };
+        apply_masked_parameter_noise(&mut b, 1234, &mask);
+        let sparsity = b.sparsity();
+        assert!(sparsity.disabled > 96 && sparsity.disabled < 160);
+        assert!(Network::with_params(b.clone()).is_ok());
+
+        // a disabled connection only changes by being enabled again
+        let disabled = b.connection_mask.as_ref().unwrap().iter().position(|&enabled| !enabled).unwrap();
+        let mut c = b.clone();
+        apply_masked_parameter_noise(&mut c, 4321, &NoiseMask::all(100));
+        assert_eq!(c.effects[disabled], Effect(0));
+
+        for &kind in &[Crossover::Uniform, Crossover::NeuronBlock, Crossover::OnePoint, Crossover::TwoPoint] {
+            let child = crossover(&a, &b, 1234, kind);
+            assert!(child.connection_mask.is_some());
+            assert!(Network::with_params(child).is_ok());
+        }
+    }
+
+    #[test]
+    fn growing_evolution() {
+        let params = EvolutionParams {
+            population_size: 8,
+            elite_count: 3,
+            crossover: Some(Crossover::Uniform),
+            power: PowerSchedule::Fixed(2),
+            connection_toggle: None,
+            unique_io: false,
+            structure: Some(StructureMutation {
+                insert_neuron: (1, 2),
+                remove_neuron: (1, 8),
+                add_connection: (1, 4),
+                remove_connection: (1, 8),
+                max_neurons: 32,
+                max_connections: 8,
+            }),
+            novelty: None,
+        };
+        let initial = build_network_from_noise(8, 3, 2, 2, 1234, iter::empty())
+            .unwrap()
+            .extract_params();
+        // reward larger networks
+        let fitness = |p: &NetworkParams| p.effects.len() as f64;
+
+        let mut evolution = Evolution::new(params, 1234, vec![initial]);
+        for _ in 0..20 {
+            evolution.step(fitness);
+        }
+
+        let best = &evolution.best().unwrap().params;
+        assert!(best.tresholds.len() > 8 && best.tresholds.len() <= 32);
+        assert!(best.effects.len() > 8 * 3);
+        assert!(evolution.population().iter().all(|i| Network::with_params(i.params.clone()).is_ok()));
+    }
+
+    #[test]
+    fn unique_io_evolution() {
+        let params = EvolutionParams {
+            population_size: 8,
+            elite_count: 3,
+            crossover: Some(Crossover::Uniform),
+            power: PowerSchedule::Fixed(8),
+            connection_toggle: None,
+            unique_io: true,
+            structure: Some(StructureMutation {
+                insert_neuron: (1, 4),
+                remove_neuron: (3, 4),
+                add_connection: (1, 8),
+                remove_connection: (1, 8),
+                max_neurons: 16,
+                max_connections: 4,
+            }),
+            novelty: None,
+        };
+        let mut initial = build_network_from_noise(12, 2, 6, 6, 1234, iter::empty())
+            .unwrap()
+            .extract_params();
+        initial.input_neurons = (0..6).collect();
+        initial.output_neurons = (3..9).collect();
+        // reward smaller networks, so IO neurons are removed often
+        let fitness = |p: &NetworkParams| -(p.tresholds.len() as f64);
+
+        let unique = |neurons: &[usize]| (1..neurons.len()).all(|i| !neurons[..i].contains(&neurons[i]));
+        let mut evolution = Evolution::new(params, 1234, vec![initial]);
+        for _ in 0..30 {
+            evolution.step(fitness);
+            for individual in evolution.population() {
+                assert!(unique(&individual.params.input_neurons));
+                assert!(unique(&individual.params.output_neurons));
+                assert!(individual.params.tresholds.len() >= 6);
+            }
+        }
+        assert_eq!(evolution.best().unwrap().params.tresholds.len(), 6);
+    }
+
+    #[test]
+    fn novelty_search() {
+        let mut params = EvolutionParams {
+            population_size: 12,
+            elite_count: 4,
+            crossover: None,
+            power: PowerSchedule::Fixed(3),
+            connection_toggle: None,
+            unique_io: false,
+            structure: None,
+            novelty: Some(Novelty {
+                k: 3,
+                weight: 1.0,
+                archive_ratio: (1, 3),
+                archive_size: 16,
+            }),
+        };
+        let initial = build_network_from_noise(16, 3, 2, 2, 1234, iter::empty())
+            .unwrap()
+            .extract_params();
+        // every individual is equally fit, only the mean effect distinguishes them
+        let evaluate = |p: &NetworkParams| Evaluation {
+            fitness: 0.0,
+            behaviour: vec![p.effects.iter().map(|e| e.0 as f64).sum::<f64>() / p.effects.len() as f64],
+        };
+        let spread = |evolution: &Evolution| {
+            let behaviours = evolution.population().iter().take(4).map(|i| i.behaviour[0]);
+            let max = behaviours.clone().fold(f64::NEG_INFINITY, f64::max);
+            let min = behaviours.fold(f64::INFINITY, f64::min);
+            max - min
+        };
+
+        let mut a = Evolution::new(params, 1234, vec![initial.clone()]);
+        let mut b = Evolution::new(params, 1234, vec![initial.clone()]);
+        for _ in 0..20 {
+            a.step_evaluated(evaluate);
+            b.step_evaluated(evaluate);
+        }
+        assert!(a.archive().len() <= 16 && !a.archive().is_empty());
+        assert_eq!(a.archive(), b.archive());
+        for (a, b) in a.population().iter().zip(b.population().iter()) {
+            assert_eq!(a.params, b.params);
+            assert_eq!(a.novelty, b.novelty);
+        }
+        assert!(a.population().iter().take(4).all(|i| i.novelty.is_some()));
+
+        params.novelty = None;
+        let mut fitness_only = Evolution::new(params, 1234, vec![initial]);
+        for _ in 0..20 {
+            fitness_only.step_evaluated(evaluate);
+        }
+        assert!(fitness_only.archive().is_empty());
+        assert!(spread(&a) > spread(&fitness_only));
+    }
+
+    const VALID_NOVELTY: Novelty = Novelty {
+        k: 3,
+        weight: 0.5,
+        archive_ratio: (1, 3),
+        archive_size: 16,
+    };
+
+    fn novelty_evolution(novelty: Novelty) -> Evolution {
+        let params = EvolutionParams {
+            population_size: 8,
+            elite_count: 2,
+            crossover: None,
+            power: PowerSchedule::Fixed(2),
+            connection_toggle: None,
+            unique_io: false,
+            structure: None,
+            novelty: Some(novelty),
+        };
+        let initial = build_network_from_noise(16, 3, 2, 2, 1234, iter::empty())
+            .unwrap()
+            .extract_params();
+        Evolution::new(params, 1234, vec![initial])
+    }
+
+    #[test]
+    #[should_panic]
+    fn novelty_zero_k() {
+        novelty_evolution(Novelty { k: 0, ..VALID_NOVELTY });
+    }
+
+    #[test]
+    #[should_panic]
+    fn novelty_weight_above_one() {
+        novelty_evolution(Novelty { weight: 1.5, ..VALID_NOVELTY });
+    }
+
+    #[test]
+    #[should_panic]
+    fn novelty_nan_weight() {
+        novelty_evolution(Novelty { weight: f64::NAN, ..VALID_NOVELTY });
+    }
+
+    #[test]
+    #[should_panic]
+    fn novelty_zero_archive_denominator() {
+        novelty_evolution(Novelty { archive_ratio: (1, 0), ..VALID_NOVELTY });
+    }
+
+    #[test]
+    #[should_panic]
+    fn novelty_archive_ratio_above_one() {
+        novelty_evolution(Novelty { archive_ratio: (4, 3), ..VALID_NOVELTY });
+    }
+
+    /// A plain fitness has no behaviour to measure novelty on.
+    #[test]
+    #[should_panic(expected = "novelty search needs behaviours")]
+    fn novelty_plain_fitness() {
+        novelty_evolution(VALID_NOVELTY).step(|_| 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "novelty search needs behaviours")]
+    fn novelty_empty_behaviour() {
+        novelty_evolution(VALID_NOVELTY).step_evaluated(|_| Evaluation { fitness: 0.0, behaviour: vec![] });
+    }
 }
@@ -0,0 +1,384 @@
+//! Saving and restoring the complete state of an [Evolution] run.
+
+use super::*;
+use crate::format::*;
+
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    iter,
+    path::Path,
+};
+
+const CHECKPOINT_MAGIC: &[u8; 8] = b"SIARNEC\0";
+const CHECKPOINT_VERSION: u32 = 5;
+
+impl Evolution {
+    /// Write the complete state of the run, so that continuing a run read back with
+    /// [Evolution::read_checkpoint] produces exactly the same generations as this run would.
+    pub fn write_checkpoint<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write_magic(w, CHECKPOINT_MAGIC, CHECKPOINT_VERSION)?;
+        write_params(w, &self.params)?;
+        write_u64(w, self.seed)?;
+        write_u64(w, self.generation)?;
+        write_u8(w, self.power)?;
+
+        write_u64(w, self.population.len() as u64)?;
+        for individual in self.population.iter() {
+            write_optional_f64(w, individual.fitness)?;
+            write_optional_f64(w, individual.parent_fitness)?;
+            write_u8(w, individual.power)?;
+            write_f64s(w, &individual.behaviour)?;
+            write_optional_f64(w, individual.novelty)?;
+            individual.params.write_to(w)?;
+        }
+
+        write_f64s(w, &self.best_fitness_history)?;
+
+        write_u64(w, self.archive.len() as u64)?;
+        for behaviour in self.archive.iter() {
+            write_f64s(w, behaviour)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read a run written by [Evolution::write_checkpoint].
+    /// # Errors
+    /// Any error of `r`, or [io::ErrorKind::InvalidData] when the data is not in the expected format
+    /// or describes a run that [Evolution::new] would not accept.
+    pub fn read_checkpoint<R: Read>(r: &mut R) -> io::Result<Self> {
+        read_magic(r, CHECKPOINT_MAGIC, CHECKPOINT_VERSION)?;
+        let params = read_params(r)?;
+        let seed = read_u64(r)?;
+        let generation = read_u64(r)?;
+        let power = read_u8(r)?;
+
+        if params.elite_count == 0 || params.elite_count > params.population_size {
+            return Err(invalid_data("invalid elite count"));
+        }
+
+        let population_len = read_usize(r)?;
+        if population_len == 0 {
+            return Err(invalid_data("empty population"));
+        }
+        let population = iter::repeat_with(|| -> io::Result<_> {
+            Ok(Individual {
+                fitness: read_optional_f64(r)?,
+                parent_fitness: read_optional_f64(r)?,
+                power: read_u8(r)?,
+                behaviour: read_f64s(r)?,
+                novelty: read_optional_f64(r)?,
+                params: NetworkParams::read_from(r)?,
+            })
+        })
+        .take(population_len)
+        .collect::<io::Result<Vec<_>>>()?;
+
+        let best_fitness_history = read_f64s(r)?;
+
+        let archive_len = read_usize(r)?;
+        let archive = iter::repeat_with(|| read_f64s(r))
+            .take(archive_len)
+            .collect::<io::Result<Vec<_>>>()?;
+
+        // unevaluated individuals get their behaviour when they are evaluated
+        let mut behaviours = population
+            .iter()
+            .filter(|i| i.fitness.is_some())
+            .map(|i| &i.behaviour)
+            .chain(archive.iter());
+        if let Some(first) = behaviours.next() {
+            if behaviours.any(|b| b.len() != first.len()) {
+                return Err(invalid_data("behaviours of different lengths"));
+            }
+        }
+
+        Ok(Self {
+            params,
+            seed,
+            generation,
+            power,
+            population,
+            best_fitness_history,
+            archive,
+        })
+    }
+
+    /// Write a checkpoint to a file, see [Evolution::write_checkpoint].  
+    /// The file is replaced atomically, so an interrupted write never loses the previous checkpoint.
+    pub fn save_checkpoint<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+
+        let mut w = BufWriter::new(File::create(&tmp_path)?);
+        self.write_checkpoint(&mut w)?;
+        w.into_inner()?.sync_all()?;
+
+        fs::rename(tmp_path, path)
+    }
+
+    /// Read a checkpoint from a file, see [Evolution::read_checkpoint].
+    pub fn load_checkpoint<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_checkpoint(&mut BufReader::new(File::open(path)?))
+    }
+}
+
+fn write_params<W: Write>(w: &mut W, params: &EvolutionParams) -> io::Result<()> {
+    write_u64(w, params.population_size as u64)?;
+    write_u64(w, params.elite_count as u64)?;
+
+    write_u8(w, match params.crossover {
+        None => 0,
+        Some(Crossover::Uniform) => 1,
+        Some(Crossover::NeuronBlock) => 2,
+        Some(Crossover::OnePoint) => 3,
+        Some(Crossover::TwoPoint) => 4,
+    })?;
+
+    match params.power {
+        PowerSchedule::Fixed(power) => w.write_all(&[0, power])?,
+        PowerSchedule::Decay { start, end, generations } => {
+            w.write_all(&[1, start, end])?;
+            write_u64(w, generations)?;
+        }
+        PowerSchedule::OneFifth { initial, min, max } => w.write_all(&[2, initial, min, max])?,
+        PowerSchedule::SelfAdaptive { initial, min, max } => w.write_all(&[3, initial, min, max])?,
+    }
+
+    match params.connection_toggle {
+        None => write_u8(w, 0)?,
+        Some(toggle) => {
+            write_u8(w, 1)?;
+            write_ratio(w, toggle)?;
+        }
+    }
+
+    write_u8(w, params.unique_io as u8)?;
+
+    match params.structure {
+        None => write_u8(w, 0)?,
+        Some(structure) => {
+            write_u8(w, 1)?;
+            write_ratio(w, structure.insert_neuron)?;
+            write_ratio(w, structure.remove_neuron)?;
+            write_ratio(w, structure.add_connection)?;
+            write_ratio(w, structure.remove_connection)?;
+            write_u64(w, structure.max_neurons as u64)?;
+            write_u64(w, structure.max_connections as u64)?;
+        }
+    }
+
+    match params.novelty {
+        None => write_u8(w, 0),
+        Some(novelty) => {
+            write_u8(w, 1)?;
+            write_u64(w, novelty.k as u64)?;
+            write_f64(w, novelty.weight)?;
+            write_ratio(w, novelty.archive_ratio)?;
+            write_u64(w, novelty.archive_size as u64)
+        }
+    }
+}
+
+fn read_params<R: Read>(r: &mut R) -> io::Result<EvolutionParams> {
+    let population_size = read_usize(r)?;
+    let elite_count = read_usize(r)?;
+
+    let crossover = match read_u8(r)? {
+        0 => None,
+        1 => Some(Crossover::Uniform),
+        2 => Some(Crossover::NeuronBlock),
+        3 => Some(Crossover::OnePoint),
+        4 => Some(Crossover::TwoPoint),
+        _ => return Err(invalid_data("invalid crossover")),
+    };
+
+    let power = match read_u8(r)? {
+        0 => PowerSchedule::Fixed(read_u8(r)?),
+        1 => PowerSchedule::Decay {
+            start: read_u8(r)?,
+            end: read_u8(r)?,
+            generations: read_u64(r)?,
+        },
+        2 => PowerSchedule::OneFifth {
+            initial: read_u8(r)?,
+            min: read_u8(r)?,
+            max: read_u8(r)?,
+        },
+        3 => PowerSchedule::SelfAdaptive {
+            initial: read_u8(r)?,
+            min: read_u8(r)?,
+            max: read_u8(r)?,
+        },
+        _ => return Err(invalid_data("invalid power schedule")),
+    };
+
+    let connection_toggle = match read_bool(r)? {
+        false => None,
+        true => Some(read_ratio(r)?),
+    };
+
+    let unique_io = read_bool(r)?;
+
+    let structure = match read_bool(r)? {
+        false => None,
+        true => Some(StructureMutation {
+            insert_neuron: read_ratio(r)?,
+            remove_neuron: read_ratio(r)?,
+            add_connection: read_ratio(r)?,
+            remove_connection: read_ratio(r)?,
+            max_neurons: read_usize(r)?,
+            max_connections: read_usize(r)?,
+        }),
+    };
+
+    let novelty = match read_bool(r)? {
+        false => None,
+        true => Some(Novelty {
+            k: read_usize(r)?,
+            weight: read_f64(r)?,
+            archive_ratio: read_ratio(r)?,
+            archive_size: read_usize(r)?,
+        }),
+    };
+    if novelty.is_some_and(|novelty| !novelty.is_valid()) {
+        return Err(invalid_data("invalid novelty"));
+    }
+
+    Ok(EvolutionParams {
+        population_size,
+        elite_count,
+        crossover,
+        power,
+        connection_toggle,
+        unique_io,
+        structure,
+        novelty,
+    })
+}
+
+fn write_ratio<W: Write>(w: &mut W, (numerator, denominator): (u32, u32)) -> io::Result<()> {
+    write_u32(w, numerator)?;
+    write_u32(w, denominator)
+}
+
+fn read_ratio<R: Read>(r: &mut R) -> io::Result<(u32, u32)> {
+    Ok((read_u32(r)?, read_u32(r)?))
+}
+
+fn write_optional_f64<W: Write>(w: &mut W, value: Option<f64>) -> io::Result<()> {
+    write_u8(w, value.is_some() as u8)?;
+    write_f64(w, value.unwrap_or(0.0))
+}
+
+fn read_optional_f64<R: Read>(r: &mut R) -> io::Result<Option<f64>> {
+    let is_some = read_bool(r)?;
+    let value = read_f64(r)?;
+    Ok(Some(value).filter(|_| is_some))
+}
+
+fn write_f64s<W: Write>(w: &mut W, values: &[f64]) -> io::Result<()> {
+    write_u64(w, values.len() as u64)?;
+    for &value in values.iter() {
+        write_f64(w, value)?;
+    }
+    Ok(())
+}
+
+fn read_f64s<R: Read>(r: &mut R) -> io::Result<Vec<f64>> {
+    let len = read_usize(r)?;
+    iter::repeat_with(|| read_f64(r))
+        .take(len)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resume() {
+        let params = EvolutionParams {
+            population_size: 8,
+            elite_count: 2,
+            crossover: Some(Crossover::TwoPoint),
+            power: PowerSchedule::OneFifth { initial: 3, min: 1, max: 6 },
+            connection_toggle: Some((1, 64)),
+            unique_io: true,
+            structure: Some(StructureMutation {
+                insert_neuron: (1, 4),
+                remove_neuron: (1, 8),
+                add_connection: (1, 8),
+                remove_connection: (1, 8),
+                max_neurons: 24,
+                max_connections: 6,
+            }),
+            novelty: Some(Novelty {
+                k: 3,
+                weight: 0.5,
+                archive_ratio: (1, 2),
+                archive_size: 20,
+            }),
+        };
+        let initial = build_network_from_noise(16, 3, 2, 2, 1234, iter::empty())
+            .unwrap()
+            .extract_params();
+        let evaluate = |p: &NetworkParams| Evaluation {
+            fitness: p.effects.iter().map(|e| e.0 as f64).sum(),
+            behaviour: vec![p.tresholds.len() as f64, p.tresholds[0].0 as f64],
+        };
+
+        let mut uninterrupted = Evolution::new(params, 1234, vec![initial.clone()]);
+        uninterrupted.run_evaluated(10, evaluate, None).unwrap();
+
+        let path = std::env::temp_dir().join(format!("siarne-checkpoint-{}", std::process::id()));
+        let checkpoint = Checkpoint {
+            path: path.clone(),
+            interval: 2,
+        };
+        let mut interrupted = Evolution::new(params, 1234, vec![initial]);
+        interrupted.run_evaluated(5, evaluate, Some(&checkpoint)).unwrap();
+
+        let mut resumed = Evolution::load_checkpoint(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(resumed.generation(), 5);
+
+        let mut buf = vec![];
+        resumed.write_checkpoint(&mut buf).unwrap();
+        let read = |buf: &[u8]| Evolution::read_checkpoint(&mut &buf[..]).map(|_| ()).map_err(|e| e.kind());
+        assert_eq!(read(&buf), Ok(()));
+
+        // the elite count directly follows the population size
+        let mut invalid = buf.clone();
+        invalid[20..28].copy_from_slice(&0u64.to_le_bytes());
+        assert_eq!(read(&invalid), Err(io::ErrorKind::InvalidData));
+        invalid[20..28].copy_from_slice(&9u64.to_le_bytes());
+        assert_eq!(read(&invalid), Err(io::ErrorKind::InvalidData));
+
+        let modified = |modify: &dyn Fn(&mut Evolution)| {
+            let mut evolution = Evolution::read_checkpoint(&mut &buf[..]).unwrap();
+            modify(&mut evolution);
+            let mut modified = vec![];
+            evolution.write_checkpoint(&mut modified).unwrap();
+            modified
+        };
+        let invalid = modified(&|e| e.archive.push(vec![0.0]));
+        assert_eq!(read(&invalid), Err(io::ErrorKind::InvalidData));
+        let invalid = modified(&|e| e.population.clear());
+        assert_eq!(read(&invalid), Err(io::ErrorKind::InvalidData));
+        let invalid = modified(&|e| e.params.novelty.as_mut().unwrap().weight = f64::NAN);
+        assert_eq!(read(&invalid), Err(io::ErrorKind::InvalidData));
+        resumed.run_evaluated(10, evaluate, None).unwrap();
+
+        assert_eq!(resumed.best_fitness_history(), uninterrupted.best_fitness_history());
+        assert_eq!(resumed.power(), uninterrupted.power());
+        assert_eq!(resumed.archive(), uninterrupted.archive());
+        for (a, b) in resumed.population().iter().zip(uninterrupted.population().iter()) {
+            assert_eq!(a.params, b.params);
+            assert_eq!(a.fitness, b.fitness);
+            assert_eq!(a.novelty, b.novelty);
+        }
+    }
+}
@@ -0,0 +1,355 @@
+//! Structural mutations that change the amount of neurons and connections of [NetworkParams].
+
+use super::*;
+use crate::network::{connection_target, Effect};
+
+/// Probabilities of the structural mutations applied by [apply_structure_mutation].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StructureMutation {
+    /// Probability of inserting a neuron at a random position, see [insert_neuron].
+    pub insert_neuron: (u32, u32),
+    /// Probability of removing a random neuron, see [remove_neuron].
+    pub remove_neuron: (u32, u32),
+    /// Probability of adding a connection to every neuron, see [set_connection_count].
+    pub add_connection: (u32, u32),
+    /// Probability of removing a connection from every neuron, see [set_connection_count].
+    pub remove_connection: (u32, u32),
+    /// Neurons are never inserted when there are this many neurons or more.
+    pub max_neurons: usize,
+    /// Connections are never added when every neuron has this many connections or more.
+    pub max_connections: usize,
+}
+
+/// Randomly insert or remove a neuron and widen or narrow the connection window of the parameters,
+/// with the probabilities in `mutation`. Mutations that would make the parameters invalid, or leave
+/// less neurons than inputs or outputs, are skipped.
+/// The result only depends on the parameters, `seed` and `mutation`.
+/// # Panics
+/// When a probability in `mutation` is not a valid probability.
+pub fn apply_structure_mutation(params: &mut NetworkParams, seed: u64, mutation: &StructureMutation) {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let [insert, remove, add, narrow] = [
+        mutation.insert_neuron,
+        mutation.remove_neuron,
+        mutation.add_connection,
+        mutation.remove_connection,
+    ]
+    .map(|ratio| distributions::Bernoulli::from_ratio(ratio.0, ratio.1).unwrap().sample(&mut rng));
+
+    let neuron_count = params.tresholds.len();
+    if insert && neuron_count < mutation.max_neurons {
+        insert_neuron(params, rng.gen_range(0..=neuron_count));
+    }
+
+    let neuron_count = params.tresholds.len();
+    let io_count = params.input_neurons.len().max(params.output_neurons.len());
+    if remove && neuron_count > io_count {
+        // fails when the neurons can not be reduced, leaving the parameters unchanged
+        let _ = remove_neuron(params, rng.gen_range(0..neuron_count));
+    }
+
+    let connection_count = params.effects.len().checked_div(params.tresholds.len()).unwrap_or(0);
+    if add && connection_count < mutation.max_connections {
+        let _ = set_connection_count(params, connection_count + 1);
+    }
+
+    let connection_count = params.effects.len().checked_div(params.tresholds.len()).unwrap_or(0);
+    if narrow {
+        let _ = set_connection_count(params, connection_count.saturating_sub(1));
+    }
+}
+
+/// Insert a neuron into the ring before the neuron at `index`, or after the last neuron when
+/// `index` is the neuron count. The new neuron copies the treshold and type of the neuron that was
+/// at `index` (wrapping around). Its effects and the effects of the connections to it are 0,
+/// so it has no influence on the other neurons until its connections change.
+/// Every other connection keeps its source, target and effect, except for the connections that end up
+/// outside the connection window of their source by the insertion, which are removed.
+/// Input and output neurons keep referring to the same neurons.
+/// # Panics
+/// When `index` is greater than the neuron count, or when there are no neurons.
+pub fn insert_neuron(params: &mut NetworkParams, index: usize) {
+    let neuron_count = params.tresholds.len();
+    assert!(index <= neuron_count && neuron_count > 0);
+    let connection_count = params.effects.len() / neuron_count;
+
+    remap_connections(params, neuron_count + 1, connection_count, |neuron| match neuron.cmp(&index) {
+        Ordering::Less => Some(neuron),
+        Ordering::Equal => None,
+        Ordering::Greater => Some(neuron - 1),
+    });
+
+    let template = index % neuron_count;
+    params.tresholds = inserted(&params.tresholds, index, params.tresholds[template]);
+    if let Some(types) = &mut params.neuron_types {
+        *types = inserted(types, index, types[template]);
+    }
+
+    for neuron in params.input_neurons.iter_mut().chain(params.output_neurons.iter_mut()) {
+        if *neuron >= index {
+            *neuron += 1;
+        }
+    }
+}
+
+/// Remove the neuron at `index` from the ring, along with its connections and the connections to it.
+/// Every other connection keeps its source, target and effect when the target is still in the
+/// connection window of its source. Connections that enter a window by the removal have an effect of 0.
+/// Input and output neurons that referred to the removed neuron move to the nearest following neuron
+/// that is not already used by another input or output (respectively), so unique indices stay unique.
+/// When every neuron is already used they move to the next neuron.
+/// # Errors
+/// [network::Error::ZeroNeurons] when this is the only neuron and [network::Error::TooManyConnections]
+/// when there would be less neurons than connections per neuron, the parameters are unchanged then.
+/// # Panics
+/// When `index` is out of bounds.
+pub fn remove_neuron(params: &mut NetworkParams, index: usize) -> Result<(), network::Error> {
+    let neuron_count = params.tresholds.len();
+    assert!(index < neuron_count);
+    let connection_count = params.effects.len() / neuron_count;
+
+    if neuron_count == 1 {
+        return Err(network::Error::ZeroNeurons);
+    }
+    if connection_count > neuron_count - 1 {
+        return Err(network::Error::TooManyConnections);
+    }
+
+    remap_connections(params, neuron_count - 1, connection_count, |neuron| {
+        Some(if neuron >= index { neuron + 1 } else { neuron })
+    });
+
+    params.tresholds = removed(&params.tresholds, index);
+    if let Some(types) = &mut params.neuron_types {
+        *types = removed(types, index);
+    }
+
+    params.input_neurons = removed_io(&params.input_neurons, index, neuron_count - 1);
+    params.output_neurons = removed_io(&params.output_neurons, index, neuron_count - 1);
+
+    Ok(())
+}
+
+/// Change the amount of connections of every neuron, widening or narrowing the connection window.
+/// Connections that stay in the window keep their effect, new connections have an effect of 0.
+/// Since a wider window always contains the narrower one, widening and then narrowing back
+/// restores the original parameters.
+/// # Errors
+/// [network::Error::ZeroConnections] when `connection_count` is 0 and
+/// [network::Error::TooManyConnections] when it is greater than the neuron count,
+/// the parameters are unchanged then.
+pub fn set_connection_count(params: &mut NetworkParams, connection_count: usize) -> Result<(), network::Error> {
+    let neuron_count = params.tresholds.len();
+    if connection_count == 0 {
+        return Err(network::Error::ZeroConnections);
+    }
+    if connection_count > neuron_count {
+        return Err(network::Error::TooManyConnections);
+    }
+
+    remap_connections(params, neuron_count, connection_count, Some);
+    Ok(())
+}
+
+/// Rebuild the effects and connection mask for `neuron_count` neurons with `connection_count`
+/// connections each, where `old` returns the index a neuron had before the change, if it existed.
+/// Connections between existing neurons keep their effect when the old source had a connection
+/// to the old target, every other connection has an effect of 0.
+/// Must be called before the tresholds are changed.
+fn remap_connections<F>(params: &mut NetworkParams, neuron_count: usize, connection_count: usize, old: F)
+where
+    F: Fn(usize) -> Option<usize>,
+{
+    let old_neuron_count = params.tresholds.len();
+    let old_connection_count = params.effects.len() / old_neuron_count;
+
+    let effect_count = neuron_count * connection_count;
+    let mut effects = vec![Effect(0); effect_count];
+    let mut mask = params.connection_mask.as_ref().map(|_| vec![true; effect_count]);
+
+    for src in 0..neuron_count {
+        for column in 0..connection_count {
+            let dst = connection_target(src, column, neuron_count, connection_count);
+            let old_index = match (old(src), old(dst)) {
+                (Some(src), Some(dst)) => {
+                    let column = (dst + old_neuron_count - src + old_connection_count / 2) % old_neuron_count;
+                    Some(src * old_connection_count + column).filter(|_| column < old_connection_count)
+                }
+                _ => None,
+            };
+
+            if let Some(old_index) = old_index {
+                let i = src * connection_count + column;
+                effects[i] = params.effects[old_index];
+                if let (Some(mask), Some(old_mask)) = (&mut mask, &params.connection_mask) {
+                    mask[i] = old_mask[old_index];
+                }
+            }
+        }
+    }
+
+    params.effects = effects.into();
+    params.connection_mask = mask.map(Into::into);
+}
+
+/// The IO neurons after removing the neuron at `index`, leaving `neuron_count` neurons.
+fn removed_io(neurons: &[usize], index: usize, neuron_count: usize) -> Box<[usize]> {
+    let mut neurons: Vec<_> = neurons
+        .iter()
+        .map(|&neuron| match neuron.cmp(&index) {
+            Ordering::Less => Some(neuron),
+            Ordering::Equal => None,
+            Ordering::Greater => Some(neuron - 1),
+        })
+        .collect();
+
+    for i in 0..neurons.len() {
+        if neurons[i].is_none() {
+            let free = (index..index + neuron_count)
+                .map(|neuron| neuron % neuron_count)
+                .find(|&neuron| !neurons.contains(&Some(neuron)));
+            neurons[i] = Some(free.unwrap_or(index % neuron_count));
+        }
+    }
+
+    neurons.into_iter().flatten().collect()
+}
+
+fn inserted<T: Copy>(values: &[T], index: usize, value: T) -> Box<[T]> {
+    let mut values = values.to_vec();
+    values.insert(index, value);
+    values.into()
+}
+
+fn removed<T: Copy>(values: &[T], index: usize) -> Box<[T]> {
+    let mut values = values.to_vec();
+    values.remove(index);
+    values.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::NeuronType;
+    use core::iter;
+    use alloc::collections::BTreeMap;
+
+    /// The effect of every connection by source and target.
+    fn connections(params: &NetworkParams) -> BTreeMap<(usize, usize), Effect> {
+        let neuron_count = params.tresholds.len();
+        let connection_count = params.effects.len() / neuron_count;
+        params.effects
+            .iter()
+            .enumerate()
+            .map(|(i, &effect)| {
+                let src = i / connection_count;
+                let dst = connection_target(src, i % connection_count, neuron_count, connection_count);
+                ((src, dst), effect)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn insert_remove() {
+        let init = InitParams { excitatory: Some((1, 2)), ..InitParams::default() };
+        let mut original = NetworkParams::random(16, 5, 3, 3, &init, &mut ChaCha8Rng::seed_from_u64(1234)).unwrap();
+        original.input_neurons = vec![0, 6, 15].into();
+        original.output_neurons = vec![6, 7, 8].into();
+        original.set_connection_enabled(7 * 5 + 1, false);
+
+        let mut params = original.clone();
+        insert_neuron(&mut params, 7);
+        assert_eq!(params.tresholds.len(), 17);
+        assert_eq!(params.effects.len(), 17 * 5);
+        assert_eq!(params.tresholds[7], original.tresholds[7]);
+        assert_eq!(params.neuron_types.as_ref().unwrap()[7], original.neuron_types.as_ref().unwrap()[7]);
+        assert_eq!(params.input_neurons.as_ref(), &[0, 6, 16]);
+        assert_eq!(params.output_neurons.as_ref(), &[6, 8, 9]);
+        assert!(Network::with_params(params.clone()).is_ok());
+
+        let old = connections(&original);
+        let new = connections(&params);
+        let to_old = |neuron: usize| if neuron > 7 { Some(neuron - 1) } else { Some(neuron).filter(|&n| n != 7) };
+        for (&(src, dst), &effect) in new.iter() {
+            match (to_old(src), to_old(dst)) {
+                (Some(src), Some(dst)) => assert_eq!(effect, old.get(&(src, dst)).copied().unwrap_or(Effect(0))),
+                _ => assert_eq!(effect, Effect(0)),
+            }
+        }
+
+        remove_neuron(&mut params, 7).unwrap();
+        assert_eq!(params.tresholds, original.tresholds);
+        assert_eq!(params.input_neurons, original.input_neurons);
+        assert_eq!(params.output_neurons, original.output_neurons);
+        let restored = connections(&params);
+        for (key, &effect) in restored.iter() {
+            assert!(effect == Effect(0) || effect == old[key]);
+        }
+        assert!(Network::with_params(params.clone()).is_ok());
+
+        // inputs and outputs on a removed neuron move to the next neuron that is not used yet
+        remove_neuron(&mut params, 15).unwrap();
+        assert_eq!(params.input_neurons.as_ref(), &[0, 6, 1]);
+        remove_neuron(&mut params, 6).unwrap();
+        assert_eq!(params.input_neurons.as_ref(), &[0, 6, 1]);
+        assert_eq!(params.output_neurons.as_ref(), &[8, 6, 7]);
+
+        // without a free neuron the next neuron is used
+        let mut full = NetworkParams::random(3, 1, 3, 1, &init, &mut ChaCha8Rng::seed_from_u64(1)).unwrap();
+        full.input_neurons = vec![0, 1, 2].into();
+        full.output_neurons = vec![2].into();
+        remove_neuron(&mut full, 2).unwrap();
+        assert_eq!(full.input_neurons.as_ref(), &[0, 1, 0]);
+        assert_eq!(full.output_neurons.as_ref(), &[0]);
+
+        let mut small = NetworkParams::random(5, 5, 1, 1, &InitParams::default(), &mut ChaCha8Rng::seed_from_u64(1)).unwrap();
+        assert!(matches!(remove_neuron(&mut small, 0), Err(network::Error::TooManyConnections)));
+        assert_eq!(small.tresholds.len(), 5);
+    }
+
+    #[test]
+    fn connection_count() {
+        let original = build_network_from_noise(16, 3, 2, 2, 1234, iter::empty()).unwrap().extract_params();
+
+        let mut params = original.clone();
+        set_connection_count(&mut params, 8).unwrap();
+        assert_eq!(params.effects.len(), 16 * 8);
+        let old = connections(&original);
+        for (key, &effect) in connections(&params).iter() {
+            assert_eq!(effect, old.get(key).copied().unwrap_or(Effect(0)));
+        }
+
+        set_connection_count(&mut params, 3).unwrap();
+        assert_eq!(params, original);
+
+        assert!(matches!(set_connection_count(&mut params, 0), Err(network::Error::ZeroConnections)));
+        assert!(matches!(set_connection_count(&mut params, 17), Err(network::Error::TooManyConnections)));
+        assert_eq!(params, original);
+    }
+
+    #[test]
+    fn structure_mutation() {
+        let mutation = StructureMutation {
+            insert_neuron: (1, 2),
+            remove_neuron: (1, 4),
+            add_connection: (1, 4),
+            remove_connection: (1, 8),
+            max_neurons: 24,
+            max_connections: 6,
+        };
+        let init = InitParams { excitatory: Some((4, 5)), ..InitParams::default() };
+        let mut params = NetworkParams::random(8, 3, 2, 2, &init, &mut ChaCha8Rng::seed_from_u64(1234)).unwrap();
+        let mut copy = params.clone();
+
+        for seed in 0..200 {
+            apply_structure_mutation(&mut params, seed, &mutation);
+            apply_structure_mutation(&mut copy, seed, &mutation);
+            assert!(params.tresholds.len() <= 24);
+            assert!(params.effects.len() <= params.tresholds.len() * 6);
+            assert!(Network::with_params(params.clone()).is_ok());
+        }
+        assert_eq!(params, copy);
+        assert!(params.tresholds.len() > 8);
+        assert!(params.neuron_types.as_ref().unwrap().contains(&NeuronType::Inhibitory));
+    }
+}
@@ -0,0 +1,306 @@
+//! A quality-diversity trainer that keeps the best parameters for every kind of behaviour.
+
+use super::{evolve::apply_parameter_noise, Evaluation};
+use crate::network::NetworkParams;
+
+use alloc::{vec, vec::Vec};
+use rand::{distributions, prelude::*};
+use rand_chacha::ChaCha8Rng;
+
+/// A dimension of the behaviour grid, `min..max` is divided into `cells` equal cells.
+/// Values outside of the range end up in the first or last cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dimension {
+    /// Lower bound of the first cell.
+    pub min: f64,
+    /// Upper bound of the last cell.
+    pub max: f64,
+    /// Amount of cells the range is divided into.
+    pub cells: usize,
+}
+
+impl Dimension {
+    fn cell(&self, value: f64) -> usize {
+        // the cast saturates, so values below min and NaN end up in the first cell
+        let cell = ((value - self.min) / (self.max - self.min) * self.cells as f64) as usize;
+        cell.min(self.cells - 1)
+    }
+}
+
+/// Parameters for a [MapElites] run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapElitesParams {
+    /// The dimensions of the behaviour grid.
+    pub grid: Vec<Dimension>,
+    /// Amount of children evaluated every iteration.
+    pub batch_size: usize,
+    /// Power of the noise applied to every child, see [apply_parameter_noise].
+    pub power: u8,
+}
+
+/// The best parameters found for a cell of the grid.
+#[derive(Debug, Clone)]
+pub struct Elite {
+    /// The parameters that were evaluated.
+    pub params: NetworkParams,
+    /// The fitness of the parameters, higher is better.
+    pub fitness: f64,
+    /// The behaviour of the parameters, which determines their cell.
+    pub behaviour: Vec<f64>,
+}
+
+/// MAP-Elites: an archive keeps the best parameters found for every cell of a grid over
+/// user defined behaviour descriptors, and every iteration children of random elites are evaluated
+/// and stored in their cell when they are better than its elite. Instead of a single best solution
+/// this finds the best solution for every behaviour, which also helps to escape local optima.
+/// All randomness is derived from the seed passed to [MapElites::new], so two runs with the same
+/// seed, initial parameters and deterministic evaluation produce the same archive.
+/// # Examples
+/// ```
+/// # use siarne::{Network, analysis::stats::NetworkStats};
+/// # use siarne::network::NetworkParams;
+/// # use siarne::train::{Evaluation, map_elites::{Dimension, MapElites, MapElitesParams}};
+/// let params = MapElitesParams {
+///     grid: vec![
+///         Dimension { min: 0.0, max: 1.0, cells: 8 },
+///         Dimension { min: 0.0, max: 1.0, cells: 8 },
+///     ],
+///     batch_size: 16,
+///     power: 2,
+/// };
+/// let initial = Network::from_seed(32, 4, 2, 2, 1234).unwrap().extract_params();
+/// let mut map_elites = MapElites::new(params, 1234, vec![initial]);
+///
+/// // firing rate × silent neurons as the behaviour
+/// let evaluate = |params: &NetworkParams| {
+///     let mut net = Network::with_params(params.clone()).unwrap();
+///     let mut stats = NetworkStats::new(32, 16);
+///     for _ in 0..50 {
+///         stats.tick(&mut net);
+///     }
+///     let firing_rate = stats.firing_rates().sum::<f64>() / 32.0;
+///     Evaluation {
+///         // the actual fitness function
+///         fitness: -(firing_rate - 0.5).abs(),
+///         behaviour: vec![firing_rate, stats.silent_fraction()],
+///     }
+/// };
+/// for _ in 0..10 {
+///     map_elites.step(evaluate);
+/// }
+/// assert!(map_elites.coverage() > 0.0);
+/// ```
+pub struct MapElites {
+    params: MapElitesParams,
+    seed: u64,
+    iteration: u64,
+    /// Initial parameters that have not been evaluated yet.
+    initial: Vec<NetworkParams>,
+    archive: Vec<Option<Elite>>,
+}
+
+impl MapElites {
+    /// Start a run from the specified initial parameters, which are evaluated by the first
+    /// call to [MapElites::step].
+    /// # Panics
+    /// When the grid has no dimensions, a dimension has no cells or an empty range,
+    /// `batch_size` is 0 or `initial` is empty.
+    pub fn new(params: MapElitesParams, seed: u64, initial: Vec<NetworkParams>) -> Self {
+        assert!(!params.grid.is_empty());
+        assert!(params.grid.iter().all(|d| d.cells > 0 && d.min < d.max));
+        assert!(params.batch_size > 0);
+        assert!(!initial.is_empty());
+
+        let cell_count = params.grid
+            .iter()
+            .try_fold(1usize, |count, d| count.checked_mul(d.cells))
+            .expect("too many cells");
+
+        Self {
+            params,
+            seed,
+            iteration: 0,
+            initial,
+            archive: vec![None; cell_count],
+        }
+    }
+
+    /// Evaluate the initial parameters on the first call, or a batch of children of random elites
+    /// otherwise, and store them in the archive where they are better than the current elite.
+    /// # Panics
+    /// When an evaluation does not have a behaviour value for every dimension of the grid.
+    pub fn step<F>(&mut self, mut evaluate: F)
+    where
+        F: FnMut(&NetworkParams) -> Evaluation,
+    {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        rng.set_stream(self.iteration);
+
+        let children = if self.initial.is_empty() {
+            let occupied: Vec<_> = self.archive.iter().flatten().collect();
+            let parent_dist = distributions::Uniform::from(0..occupied.len());
+            (0..self.params.batch_size)
+                .map(|_| {
+                    let mut params = occupied[parent_dist.sample(&mut rng)].params.clone();
+                    apply_parameter_noise(&mut params, rng.gen(), self.params.power);
+                    params
+                })
+                .collect()
+        } else {
+            core::mem::take(&mut self.initial)
+        };
+
+        for params in children {
+            let evaluation = evaluate(&params);
+            self.insert(params, evaluation);
+        }
+
+        self.iteration += 1;
+    }
+
+    /// Store `params` in the cell of its behaviour when the cell is empty or
+    /// its elite has a lower fitness, returning whether it was stored.
+    /// # Panics
+    /// When `evaluation` does not have a behaviour value for every dimension of the grid.
+    pub fn insert(&mut self, params: NetworkParams, evaluation: Evaluation) -> bool {
+        let cell = self.cell_index(&evaluation.behaviour);
+        let better = self.archive[cell]
+            .as_ref()
+            .is_none_or(|elite| evaluation.fitness > elite.fitness);
+
+        if better {
+            self.archive[cell] = Some(Elite {
+                params,
+                fitness: evaluation.fitness,
+                behaviour: evaluation.behaviour,
+            });
+        }
+        better
+    }
+
+    /// The index into [MapElites::archive] of the cell of `behaviour`.
+    /// The first dimension of the grid is the most significant.
+    /// # Panics
+    /// When `behaviour` does not have a value for every dimension of the grid.
+    pub fn cell_index(&self, behaviour: &[f64]) -> usize {
+        assert_eq!(behaviour.len(), self.params.grid.len());
+        self.params.grid
+            .iter()
+            .zip(behaviour.iter())
+            .fold(0, |index, (dimension, &value)| index * dimension.cells + dimension.cell(value))
+    }
+
+    /// The elite of every cell of the grid, see [MapElites::cell_index].
+    pub fn archive(&self) -> &[Option<Elite>] {
+        &self.archive
+    }
+
+    /// The elite with the highest fitness, if any parameters have been evaluated.
+    pub fn best(&self) -> Option<&Elite> {
+        self.archive
+            .iter()
+            .flatten()
+            .fold(None, |best: Option<&Elite>, elite| match best {
+                Some(best) if best.fitness >= elite.fitness => Some(best),
+                _ => Some(elite),
+            })
+    }
+
+    /// The fraction of cells that has an elite.
+    pub fn coverage(&self) -> f64 {
+        self.archive.iter().flatten().count() as f64 / self.archive.len() as f64
+    }
+
+    /// The sum of the fitness of all elites, a common measure of the quality and diversity
+    /// of the archive when fitness is not negative.
+    pub fn qd_score(&self) -> f64 {
+        self.archive.iter().flatten().map(|elite| elite.fitness).sum()
+    }
+
+    /// The amount of completed calls to [MapElites::step].
+    pub fn iteration(&self) -> u64 {
+        self.iteration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::train::evolve::build_network_from_noise;
+    use core::iter;
+
+    fn evaluate(params: &NetworkParams) -> Evaluation {
+        let positive = |values: &mut dyn Iterator<Item = bool>| {
+            let (count, total) = values.fold((0, 0), |(c, t), p| (c + p as usize, t + 1));
+            count as f64 / total as f64
+        };
+        Evaluation {
+            fitness: params.effects.iter().map(|e| e.0 as f64 - i8::MIN as f64).sum(),
+            behaviour: vec![
+                positive(&mut params.tresholds.iter().map(|t| t.0 > 0)),
+                positive(&mut params.input_neurons.iter().map(|&n| n < 8)),
+            ],
+        }
+    }
+
+    #[test]
+    fn cells() {
+        let params = MapElitesParams {
+            grid: vec![
+                Dimension { min: 0.0, max: 1.0, cells: 4 },
+                Dimension { min: -1.0, max: 1.0, cells: 2 },
+            ],
+            batch_size: 1,
+            power: 1,
+        };
+        let initial = build_network_from_noise(16, 3, 2, 2, 1234, iter::empty()).unwrap().extract_params();
+        let mut map_elites = MapElites::new(params, 1234, vec![initial.clone()]);
+        assert_eq!(map_elites.archive().len(), 8);
+
+        assert_eq!(map_elites.cell_index(&[0.0, -1.0]), 0);
+        assert_eq!(map_elites.cell_index(&[0.3, 0.5]), 3);
+        assert_eq!(map_elites.cell_index(&[1.0, 1.0]), 7);
+        assert_eq!(map_elites.cell_index(&[-5.0, f64::NAN]), 0);
+        assert_eq!(map_elites.cell_index(&[5.0, 0.0]), 7);
+
+        let evaluation = |fitness| Evaluation { fitness, behaviour: vec![0.6, 0.0] };
+        assert!(map_elites.insert(initial.clone(), evaluation(1.0)));
+        assert!(!map_elites.insert(initial.clone(), evaluation(1.0)));
+        assert!(map_elites.insert(initial, evaluation(2.0)));
+        assert_eq!(map_elites.archive()[5].as_ref().unwrap().fitness, 2.0);
+        assert_eq!(map_elites.coverage(), 1.0 / 8.0);
+    }
+
+    #[test]
+    fn determinism() {
+        let params = MapElitesParams {
+            grid: vec![
+                Dimension { min: 0.0, max: 1.0, cells: 5 },
+                Dimension { min: 0.0, max: 1.0, cells: 3 },
+            ],
+            batch_size: 8,
+            power: 3,
+        };
+        let initial = build_network_from_noise(16, 3, 4, 2, 1234, iter::empty()).unwrap().extract_params();
+
+        let mut a = MapElites::new(params.clone(), 1234, vec![initial.clone()]);
+        let mut b = MapElites::new(params, 1234, vec![initial]);
+        let mut qd_scores = vec![];
+        for _ in 0..30 {
+            a.step(evaluate);
+            b.step(evaluate);
+            qd_scores.push(a.qd_score());
+        }
+
+        assert_eq!(a.iteration(), 30);
+        assert!(a.coverage() > 1.0 / 15.0);
+        assert!(qd_scores.windows(2).all(|w| w[1] >= w[0]));
+        assert_eq!(a.best().unwrap().fitness, b.best().unwrap().fitness);
+        for (cell, (elite, other)) in a.archive().iter().zip(b.archive().iter()).enumerate() {
+            assert_eq!(elite.as_ref().map(|e| &e.params), other.as_ref().map(|e| &e.params));
+            if let Some(elite) = elite {
+                assert_eq!(a.cell_index(&elite.behaviour), cell);
+            }
+        }
+    }
+}
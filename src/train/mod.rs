@@ -1,3 +1,17 @@
-//! Code related to tweaking the parameters of [Network]s to get desired output from them.
+//! Code related to tweaking the parameters of [Network](crate::Network)s to get desired output from them.
 
+use alloc::vec::Vec;
+
+pub mod crossover;
 pub mod evolve;
+pub mod map_elites;
+
+/// The result of evaluating a set of parameters,
+/// see [map_elites::MapElites::step] and [evolve::Evolution::step_evaluated].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    /// Higher is better.
+    pub fitness: f64,
+    /// A characterisation of the behaviour of the parameters, with a value per dimension.
+    pub behaviour: Vec<f64>,
+}
@@ -0,0 +1,60 @@
+/* Exercises the C API, compiled and run by tests/capi.rs. */
+#include <stdio.h>
+#include <string.h>
+
+#include "siarne.h"
+
+#define CHECK(expr) \
+    do { \
+        if (!(expr)) { \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #expr); \
+            return 1; \
+        } \
+    } while (0)
+
+int main(int argc, char **argv) {
+    const char *path = argc > 1 ? argv[1] : "capi-test.params";
+
+    /* the network from the tick_io test in src/network.rs */
+    int32_t tresholds[] = {0, 16, -16};
+    int8_t effects[] = {
+        -8, 1, 2,
+        -17, 127, 0,
+        127, 0, 0,
+    };
+    size_t input_neurons[] = {2, 0};
+    size_t output_neurons[] = {2, 0, 1};
+
+    SiarneNetwork *net = NULL;
+    CHECK(siarne_network_from_params(tresholds, 3, effects, 9, input_neurons, 2, output_neurons, 3, &net) == SIARNE_ERROR_OK);
+    CHECK(siarne_network_neuron_count(net) == 3);
+    CHECK(siarne_network_input_count(net) == 2);
+    CHECK(siarne_network_output_count(net) == 3);
+
+    siarne_network_tick(net);
+    siarne_network_tick(net);
+    int32_t inputs[] = {-9, 0};
+    CHECK(siarne_network_apply_inputs(net, inputs, 2) == SIARNE_ERROR_OK);
+    siarne_network_tick(net);
+
+    int32_t outputs[3];
+    CHECK(siarne_network_read_outputs(net, outputs, 3) == SIARNE_ERROR_OK);
+    CHECK(outputs[0] == 0 && outputs[1] == -17 && outputs[2] == 127);
+    CHECK(siarne_network_read_outputs(net, outputs, 2) == SIARNE_ERROR_INVALID_LENGTH);
+
+    CHECK(siarne_network_save(net, path) == SIARNE_ERROR_OK);
+    siarne_network_free(net);
+
+    SiarneNetwork *loaded = NULL;
+    CHECK(siarne_network_load(path, &loaded) == SIARNE_ERROR_OK);
+    CHECK(siarne_network_output_count(loaded) == 3);
+    siarne_network_free(loaded);
+
+    CHECK(siarne_network_new(1, 2, 0, 0, 1234, &net) == SIARNE_ERROR_TOO_MANY_CONNECTIONS);
+    CHECK(siarne_network_load("/nonexistent/siarne.params", &net) == SIARNE_ERROR_IO);
+
+    size_t bad_inputs[] = {3};
+    CHECK(siarne_network_from_params(tresholds, 3, effects, 9, bad_inputs, 1, NULL, 0, &net) == SIARNE_ERROR_INVALID_NEURON_INDEX);
+
+    return 0;
+}
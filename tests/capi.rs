@@ -0,0 +1,42 @@
+//! Compiles `tests/capi.c` against the static library and runs it.
+#![cfg(feature = "capi")]
+
+use std::{env, path::PathBuf, process::Command};
+
+#[test]
+fn c_program() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // the static library is not a default crate type, so build it separately
+    let target_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("capi");
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned());
+
+    let status = Command::new(cargo)
+        .args(["rustc", "--lib", "--features", "capi", "--crate-type", "staticlib", "--target-dir"])
+        .arg(&target_dir)
+        .arg("--manifest-path")
+        .arg(manifest_dir.join("Cargo.toml"))
+        .status()
+        .expect("failed to run cargo");
+    assert!(status.success());
+
+    let lib = target_dir.join("debug").join("libsiarne.a");
+    let program = target_dir.join("capi-test");
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+
+    let status = Command::new(compiler)
+        .args(["-std=c99", "-Wall", "-Werror", "-I"])
+        .arg(manifest_dir.join("include"))
+        .arg(manifest_dir.join("tests").join("capi.c"))
+        .arg(&lib)
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&program)
+        .status()
+        .expect("failed to run the C compiler");
+    assert!(status.success());
+
+    let status = Command::new(&program)
+        .arg(target_dir.join("capi-test.params"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
@@ -0,0 +1,74 @@
+//! Runs the `siarne` binary.
+#![cfg(feature = "std")]
+
+use siarne::network::NetworkParams;
+use std::{
+    fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+};
+
+fn siarne_output(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_siarne"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run siarne");
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+fn siarne(args: &[&str], stdin: &str) -> String {
+    let output = siarne_output(args, stdin);
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn run_without_inputs() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("cli-no-inputs");
+    let path = path.to_str().unwrap();
+    siarne(&["new", path, "--neurons", "16", "--connections", "3", "--outputs", "2"], "");
+
+    // every empty line is a tick
+    let output = siarne(&["run", path], "\n\n\n");
+    assert_eq!(output.lines().count(), 3);
+    assert!(output.lines().all(|line| line.split(',').count() == 2));
+}
+
+#[test]
+fn invalid_params() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let valid = dir.join("cli-valid");
+    siarne(&["new", valid.to_str().unwrap(), "--neurons", "4", "--connections", "2", "--outputs", "1"], "");
+    let valid = NetworkParams::load(&valid).unwrap();
+
+    let out_of_range = NetworkParams { output_neurons: vec![99].into(), ..valid.clone() };
+    let no_neurons = NetworkParams {
+        tresholds: vec![].into(),
+        effects: vec![].into(),
+        neuron_types: None,
+        connection_mask: None,
+        ..valid
+    };
+    for (name, params) in [("cli-out-of-range", out_of_range), ("cli-no-neurons", no_neurons)] {
+        let path = dir.join(name);
+        params.save(&path).unwrap();
+        let path = path.to_str().unwrap();
+        let original = fs::read(path).unwrap();
+
+        for args in [&["mutate", path, "--seed", "1", "--power", "2"][..], &["prune", path, "--magnitude", "8"]] {
+            let output = siarne_output(args, "");
+            assert!(!output.status.success());
+            assert!(String::from_utf8(output.stderr).unwrap().starts_with("invalid params"));
+            assert_eq!(fs::read(path).unwrap(), original);
+        }
+
+        let output = siarne(&["inspect", path], "");
+        assert!(output.lines().any(|line| line.starts_with("invalid params")));
+    }
+}
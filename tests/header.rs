@@ -0,0 +1,30 @@
+//! Checks that `include/siarne.h` is the header cbindgen generates from `src/capi.rs`.
+//! Run with `SIARNE_BLESS_HEADER=1` to write the generated header instead.
+#![cfg(feature = "capi")]
+
+use std::{env, fs, path::PathBuf};
+
+#[test]
+fn header_up_to_date() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config::from_file(manifest_dir.join("cbindgen.toml")).unwrap();
+
+    let mut generated = vec![];
+    cbindgen::Builder::new()
+        .with_src(manifest_dir.join("src").join("capi.rs"))
+        .with_config(config)
+        .generate()
+        .expect("failed to generate the header")
+        .write(&mut generated);
+
+    let path = manifest_dir.join("include").join("siarne.h");
+    if env::var_os("SIARNE_BLESS_HEADER").is_some() {
+        fs::write(&path, &generated).unwrap();
+    } else {
+        let current = fs::read(&path).unwrap();
+        assert!(
+            current == generated,
+            "include/siarne.h is out of date, run `SIARNE_BLESS_HEADER=1 cargo test --features capi --test header`",
+        );
+    }
+}
@@ -0,0 +1,54 @@
+# Smoke test of the Python bindings, run by tests/python.rs with the built module on the path.
+import os
+import tempfile
+
+import numpy as np
+import siarne
+
+net = siarne.build_network_from_noise(32, 4, 2, 3, 1234, [(1, 2), (2, 3)])
+params = net.params
+assert params.tresholds.dtype == np.int32 and params.tresholds.shape == (32,)
+assert params.effects.dtype == np.int8 and params.effects.shape == (32 * 4,)
+assert len(params.input_neurons) == 2 and len(params.output_neurons) == 3
+
+# the arrays borrow the parameters and must not be writable
+for array in (params.tresholds, params.effects):
+    assert not array.flags.writeable
+    try:
+        array[0] = 1
+        raise AssertionError("wrote to a read-only array")
+    except ValueError:
+        pass
+    try:
+        array.setflags(write=True)
+        raise AssertionError("made a borrowed array writable")
+    except ValueError:
+        pass
+
+net.apply_inputs(np.array([100, -5], dtype=np.int32))
+net.tick()
+assert net.read_outputs().shape == (3,)
+assert net.accumulators.shape == (32,)
+assert net.run(10).shape == (10, 3)
+try:
+    net.apply_inputs(np.zeros(3, dtype=np.int32))
+    raise AssertionError("accepted the wrong amount of inputs")
+except ValueError:
+    pass
+
+# the same parameters produce the same outputs
+a = siarne.Network(params)
+b = siarne.Network(params)
+assert (a.run(20) == b.run(20)).all()
+
+path = os.path.join(tempfile.mkdtemp(), "params")
+params.save(path)
+loaded = siarne.NetworkParams.load(path)
+assert (loaded.tresholds == params.tresholds).all()
+assert (loaded.effects == params.effects).all()
+assert loaded.input_neurons == params.input_neurons
+assert loaded.output_neurons == params.output_neurons
+
+copy = siarne.NetworkParams(params.tresholds, params.effects, params.input_neurons, params.output_neurons)
+copy.apply_noise(1, 3)
+assert (copy.effects != params.effects).any()
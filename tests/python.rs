@@ -0,0 +1,44 @@
+//! Builds the Python extension module and runs `tests/python.py` with it.
+#![cfg(feature = "python")]
+
+use std::{env, fs, path::PathBuf, process::Command};
+
+#[test]
+fn python_module() {
+    let python = env::var("PYTHON").unwrap_or_else(|_| "python3".to_owned());
+    let has_numpy = Command::new(&python)
+        .args(["-c", "import numpy"])
+        .status()
+        .is_ok_and(|status| status.success());
+    if !has_numpy {
+        // CI must run the smoke test, elsewhere Python is optional
+        assert!(env::var_os("CI").is_none(), "{} with numpy is required in CI", python);
+        eprintln!("skipping, {} with numpy is not available", python);
+        return;
+    }
+
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // the extension module is not a default crate type, so build it separately
+    let target_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("python");
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned());
+
+    let status = Command::new(cargo)
+        .args(["rustc", "--lib", "--features", "python", "--crate-type", "cdylib", "--target-dir"])
+        .arg(&target_dir)
+        .arg("--manifest-path")
+        .arg(manifest_dir.join("Cargo.toml"))
+        .status()
+        .expect("failed to run cargo");
+    assert!(status.success());
+
+    let module_dir = target_dir.join("module");
+    fs::create_dir_all(&module_dir).unwrap();
+    fs::copy(target_dir.join("debug").join("libsiarne.so"), module_dir.join("siarne.so")).unwrap();
+
+    let status = Command::new(&python)
+        .arg(manifest_dir.join("tests").join("python.py"))
+        .env("PYTHONPATH", &module_dir)
+        .status()
+        .expect("failed to run python");
+    assert!(status.success());
+}